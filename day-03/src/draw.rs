@@ -0,0 +1,136 @@
+use crate::wire::{Crossing, Wire};
+use grid::{BoundingBox, Point, SparseGrid};
+use std::fmt::Write;
+
+/// Stroke colors for the wires, reused if there are more wires than colors.
+const COLORS: [&str; 6] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#9467bd", "#8c564b", "#e377c2",
+];
+
+/// Draws the wires a character per point like the puzzle's examples, with
+/// `o` at the origin, `+` at corners and `X` where wires cross. This is only
+/// practical for short wires.
+pub fn ascii(wires: &[Wire], crossings: &[Crossing]) -> String {
+    // each cell remembers which wire drew it, so a wire crossing itself gets
+    // a `+` rather than an `X`
+    let mut cells: SparseGrid<(usize, char)> = SparseGrid::new();
+    for (index, wire) in wires.iter().enumerate() {
+        let corners: Vec<Point> = wire.corners().collect();
+        for (i, ends) in corners.windows(2).enumerate() {
+            let step = (ends[1] - ends[0]).reduced();
+            let line = if step.x == 0 { '|' } else { '-' };
+            let last_segment = i + 2 == corners.len();
+            let mut point = ends[0];
+            while point != ends[1] {
+                point += step;
+                let mut c = if point == ends[1] && !last_segment {
+                    '+'
+                } else {
+                    line
+                };
+                if let Some(&(other, drawn)) = cells.get(point) {
+                    if other == index && drawn != c {
+                        c = '+';
+                    }
+                }
+                cells.insert(point, (index, c));
+            }
+        }
+    }
+    for crossing in crossings {
+        cells.insert(crossing.point, (wires.len(), 'X'));
+    }
+    cells.insert(Point::ORIGIN, (wires.len(), 'o'));
+    cells.render(|cell| cell.map_or('.', |&(_, c)| c))
+}
+
+/// Draws the wires as an SVG picture, with crossings in red and the origin
+/// in black. Unlike `ascii` the size doesn't depend on how long the wires are.
+pub fn svg(wires: &[Wire], crossings: &[Crossing]) -> String {
+    let corners = wires.iter().flat_map(|wire| wire.corners());
+    let bounds = BoundingBox::from_points(corners)
+        .unwrap_or_else(|| BoundingBox::new(Point::ORIGIN, Point::ORIGIN));
+    let size = bounds.width().max(bounds.height());
+    let margin = (size / 20).max(1) as i64;
+    let radius = (size as f64 / 200.0).max(0.5);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        bounds.min.x - margin,
+        bounds.min.y - margin,
+        bounds.width() as i64 + 2 * margin,
+        bounds.height() as i64 + 2 * margin
+    )
+    .unwrap();
+    for (index, wire) in wires.iter().enumerate() {
+        let points: Vec<_> = wire
+            .corners()
+            .map(|point| format!("{},{}", point.x, point.y))
+            .collect();
+        writeln!(
+            svg,
+            // keeps lines a pixel wide however far the picture is scaled down
+            r#"  <polyline points="{}" fill="none" stroke="{}" stroke-width="1" vector-effect="non-scaling-stroke"/>"#,
+            points.join(" "),
+            COLORS[index % COLORS.len()]
+        )
+        .unwrap();
+    }
+    let dots = crossings
+        .iter()
+        .map(|crossing| (crossing.point, "red"))
+        .chain(std::iter::once((Point::ORIGIN, "black")));
+    for (point, color) in dots {
+        writeln!(
+            svg,
+            r#"  <circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            point.x, point.y, radius, color
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wire::crossings;
+
+    fn example() -> Vec<Wire> {
+        vec!["R8,U5,L5,D3", "U7,R6,D4,L4"]
+            .into_iter()
+            .map(|line| line.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_ascii() {
+        let wires = example();
+        let expected = "\
+            +-----+..\n\
+            |.....|..\n\
+            |..+--X-+\n\
+            |..|..|.|\n\
+            |.-X--+.|\n\
+            |..|....|\n\
+            |.......|\n\
+            o-------+\n";
+        assert_eq!(ascii(&wires, &crossings(&wires)), expected);
+    }
+
+    #[test]
+    fn test_svg() {
+        let wires = example();
+        let svg = svg(&wires, &crossings(&wires));
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-1 -8 11 10">"#)
+        );
+        assert!(svg.contains(r#"points="0,0 0,-7 6,-7 6,-3 2,-3""#));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -1,120 +1,90 @@
-use std::collections::HashSet;
+mod draw;
+mod wire;
 
-const INPUT: &str = include_str!("../input.txt");
-
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn from_char(c: char) -> Self {
-        use Direction::*;
-        match c {
-            'U' => Up,
-            'D' => Down,
-            'L' => Left,
-            'R' => Right,
-            _ => panic!("Invalid char"),
-        }
-    }
-}
+use std::{env, fs};
+use wire::{closest, crossings, pairwise, shared_by, Distance, Wire};
 
-type Steps = u32;
-type Path = Vec<(Direction, Steps)>;
-type Position = (i32, i32);
+const INPUT: &str = include_str!("../input.txt");
 
+// Pass a path as the first argument to save a drawing of the wires, as SVG
+// if it ends in `.svg` and as text otherwise.
 fn main() {
-    let (path_a, path_b) = parse_input();
-    println!("Part one: {}", solve_part_one(&path_a, &path_b));
-    println!("Part two: {}", solve_part_two(&path_a, &path_b));
-}
-
-fn parse_input() -> (Path, Path) {
-    let mut lines = INPUT.lines();
-    let path_a = parse_path(lines.next().unwrap());
-    let path_b = parse_path(lines.next().unwrap());
-    (path_a, path_b)
-}
-
-fn parse_path(path: &str) -> Path {
-    path.split(',')
-        .map(|step| {
-            let direction = Direction::from_char(step.chars().next().unwrap());
-            let steps = step[1..].parse().unwrap();
-            (direction, steps)
-        })
-        .collect()
-}
-
-fn trace_path(path: &Path) -> Vec<Position> {
-    let mut positions = vec![];
-    let mut position = (0, 0);
-    use Direction::*;
-    for &(direction, steps) in path {
-        for _ in 0..steps {
-            let (x, y) = position;
-            position = match direction {
-                Up => (x, y + 1),
-                Down => (x, y - 1),
-                Right => (x + 1, y),
-                Left => (x - 1, y),
-            };
-            positions.push(position);
-        }
+    let wires = parse_input();
+    println!("Part one: {}", solve_part_one(&wires));
+    println!("Part two: {}", solve_part_two(&wires));
+    if wires.len() > 2 {
+        print_wire_pairs(&wires);
+    }
+    if let Some(path) = env::args().nth(1) {
+        let crossings = crossings(&wires);
+        let drawing = if path.ends_with(".svg") {
+            draw::svg(&wires, &crossings)
+        } else {
+            draw::ascii(&wires, &crossings)
+        };
+        fs::write(path, drawing).expect("Failed writing drawing");
     }
-    positions
 }
 
-fn manhattan_distance_from_origin(p: &Position) -> u32 {
-    let (x, y) = p;
-    x.abs() as u32 + y.abs() as u32
+/// One wire per non-blank line.
+fn parse_input() -> Vec<Wire> {
+    INPUT
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.parse().unwrap_or_else(|e| panic!("{}", e)))
+        .collect()
 }
 
-fn solve_part_one(path_a: &Path, path_b: &Path) -> u32 {
-    let a_positions: HashSet<_> = trace_path(path_a).into_iter().collect();
-    let b_positions: HashSet<_> = trace_path(path_b).into_iter().collect();
-    let common_positions = a_positions.intersection(&b_positions);
-    common_positions
-        .map(manhattan_distance_from_origin)
-        .min()
+fn solve_part_one(wires: &[Wire]) -> u64 {
+    let crossings = crossings(wires);
+    closest(&crossings, Distance::Manhattan)
         .unwrap()
+        .distance_from_origin()
 }
 
-fn steps_to_position(path_trace: &[Position], position: Position) -> u32 {
-    path_trace.iter().position(|&p| p == position).unwrap() as u32 + 1
+fn solve_part_two(wires: &[Wire]) -> u64 {
+    let crossings = crossings(wires);
+    closest(&crossings, Distance::Steps)
+        .unwrap()
+        .combined_steps()
 }
 
-fn solve_part_two(path_a: &Path, path_b: &Path) -> u32 {
-    let a_positions = trace_path(path_a);
-    let b_positions = trace_path(path_b);
-    let a_position_set = a_positions.iter().collect::<HashSet<_>>();
-    let b_position_set = b_positions.iter().collect::<HashSet<_>>();
-    let common_positions = a_position_set.intersection(&b_position_set);
-    common_positions
-        .map(|&&pos| steps_to_position(&a_positions, pos) + steps_to_position(&b_positions, pos))
-        .min()
-        .unwrap()
+/// Answers both parts for each pair of wires, and lists the points where
+/// every wire meets.
+fn print_wire_pairs(wires: &[Wire]) {
+    let crossings = crossings(wires);
+    for ((a, b), pair_crossings) in pairwise(&crossings) {
+        let nearest = closest(&pair_crossings, Distance::Manhattan).unwrap();
+        let shortest = closest(&pair_crossings, Distance::Steps).unwrap();
+        println!(
+            "Wires {} and {}: {} crossings, nearest {} at {}, fewest steps {} at {}",
+            a,
+            b,
+            pair_crossings.len(),
+            nearest.distance_from_origin(),
+            nearest.point,
+            shortest.combined_steps(),
+            shortest.point
+        );
+    }
+    for crossing in shared_by(&crossings, wires.len()) {
+        println!("All wires cross at {}", crossing.point);
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    const PART_ONE_SOLUTION: u32 = 5357;
-    const PART_TWO_SOLUTION: u32 = 101956;
+    const PART_ONE_SOLUTION: u64 = 5357;
+    const PART_TWO_SOLUTION: u64 = 101956;
 
     #[test]
     fn part_one() {
-        let (path_a, path_b) = parse_input();
-        assert_eq!(solve_part_one(&path_a, &path_b), PART_ONE_SOLUTION);
+        assert_eq!(solve_part_one(&parse_input()), PART_ONE_SOLUTION);
     }
 
     #[test]
     fn part_two() {
-        let (path_a, path_b) = parse_input();
-        assert_eq!(solve_part_two(&path_a, &path_b), PART_TWO_SOLUTION);
+        assert_eq!(solve_part_two(&parse_input()), PART_TWO_SOLUTION);
     }
 }
@@ -0,0 +1,460 @@
+use grid::{Direction, Point};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A wire laid out from the origin as a chain of straight segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wire {
+    segments: Vec<Segment>,
+}
+
+/// A straight run of wire, including both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: Point,
+    end: Point,
+    /// How far along the wire `start` is.
+    steps: u64,
+}
+
+impl Wire {
+    /// Lays out the wire a move at a time, where each move is a direction and
+    /// a number of steps.
+    ///
+    /// Fails with the index of the first move that takes the wire more than
+    /// `i64::MAX` from the origin either way, or more than `u64::MAX` steps
+    /// along it.
+    pub fn new(moves: impl IntoIterator<Item = (Direction, u64)>) -> Result<Self, usize> {
+        let mut segments = vec![];
+        let mut start = Point::ORIGIN;
+        let mut steps: u64 = 0;
+        for (index, (direction, length)) in moves.into_iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+            let end = move_from(start, direction, length).ok_or(index)?;
+            segments.push(Segment { start, end, steps });
+            start = end;
+            steps = steps.checked_add(length).ok_or(index)?;
+        }
+        Ok(Self { segments })
+    }
+
+    /// The origin followed by the end of each segment.
+    pub fn corners(&self) -> impl Iterator<Item = Point> + '_ {
+        let ends = self.segments.iter().map(|segment| segment.end);
+        std::iter::once(Point::ORIGIN).chain(ends)
+    }
+}
+
+/// Where `length` steps in `direction` from `start` ends up, if both
+/// coordinates stay within `i64::MAX` of the origin.
+fn move_from(start: Point, direction: Direction, length: u64) -> Option<Point> {
+    let length = i64::try_from(length).ok()?;
+    let vector = direction.vector();
+    // the vector is a single step, so multiplying it can't overflow
+    let coordinate = |start: i64, step: i64| {
+        start
+            .checked_add(step * length)
+            .filter(|&coordinate| coordinate != i64::MIN)
+    };
+    Some(Point::new(
+        coordinate(start.x, vector.x)?,
+        coordinate(start.y, vector.y)?,
+    ))
+}
+
+impl FromStr for Wire {
+    type Err = ParseWireError;
+
+    /// Parses moves like `R75,D30`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<_> = s.trim().split(',').collect();
+        let error = |index: usize| ParseWireError {
+            index,
+            token: tokens[index].to_string(),
+        };
+        let moves = tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| {
+                let mut chars = token.chars();
+                let direction = chars.next().and_then(Direction::from_letter);
+                let length = chars
+                    .as_str()
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|&length| i64::try_from(length).is_ok());
+                direction.zip(length).ok_or_else(|| error(index))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(moves).map_err(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWireError {
+    /// Which move is wrong, counting from 0.
+    pub index: usize,
+    pub token: String,
+}
+
+impl fmt::Display for ParseWireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {}: invalid move {:?}", self.index, self.token)
+    }
+}
+
+impl std::error::Error for ParseWireError {}
+
+impl Segment {
+    fn is_horizontal(&self) -> bool {
+        self.start.y == self.end.y
+    }
+
+    /// The lowest and highest coordinates along the segment's own axis.
+    fn span(&self) -> (i64, i64) {
+        let (a, b) = if self.is_horizontal() {
+            (self.start.x, self.end.x)
+        } else {
+            (self.start.y, self.end.y)
+        };
+        (a.min(b), a.max(b))
+    }
+
+    /// The coordinate that is the same all along the segment.
+    fn line(&self) -> i64 {
+        if self.is_horizontal() {
+            self.start.y
+        } else {
+            self.start.x
+        }
+    }
+
+    fn point_at(&self, coordinate: i64) -> Point {
+        if self.is_horizontal() {
+            Point::new(coordinate, self.start.y)
+        } else {
+            Point::new(self.start.x, coordinate)
+        }
+    }
+
+    /// How far along the wire `point` is, if it's on this segment.
+    fn steps_to(&self, point: Point) -> u64 {
+        self.steps + self.start.manhattan_distance(point)
+    }
+}
+
+/// A point other than the origin where two or more wires meet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crossing {
+    pub point: Point,
+    /// The wires that meet here, by index, with the fewest steps each takes
+    /// to reach the point.
+    pub steps: Vec<(usize, u64)>,
+}
+
+/// Ways of measuring how far away a crossing is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distance {
+    /// The Manhattan distance from the origin.
+    Manhattan,
+    /// The steps every wire meeting there takes to reach it, added up.
+    Steps,
+}
+
+impl Crossing {
+    pub fn distance_from_origin(&self) -> u64 {
+        self.point.manhattan_distance(Point::ORIGIN)
+    }
+
+    pub fn combined_steps(&self) -> u64 {
+        self.steps.iter().map(|&(_, steps)| steps).sum()
+    }
+
+    pub fn distance(&self, distance: Distance) -> u64 {
+        match distance {
+            Distance::Manhattan => self.distance_from_origin(),
+            Distance::Steps => self.combined_steps(),
+        }
+    }
+
+    pub fn wire_count(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+/// The crossing with the smallest `distance`, picking the first by position
+/// if there's a tie.
+pub fn closest(crossings: &[Crossing], distance: Distance) -> Option<&Crossing> {
+    crossings
+        .iter()
+        .min_by_key(|crossing| (crossing.distance(distance), crossing.point))
+}
+
+/// The crossings where at least `count` wires meet.
+pub fn shared_by(crossings: &[Crossing], count: usize) -> impl Iterator<Item = &Crossing> {
+    crossings
+        .iter()
+        .filter(move |crossing| crossing.wire_count() >= count)
+}
+
+/// The crossings between each pair of wires, keyed by the indices of the
+/// wires with the lower first. Each crossing only has the steps of that pair.
+pub fn pairwise(crossings: &[Crossing]) -> BTreeMap<(usize, usize), Vec<Crossing>> {
+    let mut pairs: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for crossing in crossings {
+        for (i, &a) in crossing.steps.iter().enumerate() {
+            for &b in &crossing.steps[i + 1..] {
+                pairs.entry((a.0, b.0)).or_default().push(Crossing {
+                    point: crossing.point,
+                    steps: vec![a, b],
+                });
+            }
+        }
+    }
+    pairs
+}
+
+type WireSegment<'a> = (usize, &'a Segment);
+
+/// Every point where different wires meet, sorted by position. A wire
+/// crossing itself doesn't count.
+///
+/// Wires are compared a segment at a time, so the work depends on how many
+/// turns they make rather than how long they are, except where wires run
+/// along each other and every shared point is a crossing.
+pub fn crossings(wires: &[Wire]) -> Vec<Crossing> {
+    let segments: Vec<WireSegment> = wires
+        .iter()
+        .enumerate()
+        .flat_map(|(wire, w)| w.segments.iter().map(move |segment| (wire, segment)))
+        .collect();
+    let mut found: HashMap<Point, BTreeMap<usize, u64>> = HashMap::new();
+    let mut record = |point: Point, pair: [WireSegment; 2]| {
+        if point == Point::ORIGIN {
+            return;
+        }
+        let steps = found.entry(point).or_default();
+        for (wire, segment) in pair.iter() {
+            let to_point = segment.steps_to(point);
+            let fewest = steps.entry(*wire).or_insert(to_point);
+            *fewest = (*fewest).min(to_point);
+        }
+    };
+    perpendicular_crossings(&segments, &mut record);
+    collinear_overlaps(&segments, &mut record);
+
+    let mut crossings: Vec<_> = found
+        .into_iter()
+        .map(|(point, steps)| Crossing {
+            point,
+            steps: steps.into_iter().collect(),
+        })
+        .collect();
+    crossings.sort_unstable_by_key(|crossing| crossing.point);
+    crossings
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Event {
+    // the order matters, so segments touching at an end still cross
+    HorizontalStart,
+    Vertical,
+    HorizontalEnd,
+}
+
+/// Finds horizontal segments crossing vertical ones by sweeping a line
+/// across from left to right, keeping track of the horizontal segments it
+/// passes through.
+fn perpendicular_crossings<F>(segments: &[WireSegment], record: &mut F)
+where
+    F: FnMut(Point, [WireSegment; 2]),
+{
+    let mut events = vec![];
+    for (index, (_, segment)) in segments.iter().enumerate() {
+        if segment.is_horizontal() {
+            let (left, right) = segment.span();
+            events.push((left, Event::HorizontalStart, index));
+            events.push((right, Event::HorizontalEnd, index));
+        } else {
+            events.push((segment.line(), Event::Vertical, index));
+        }
+    }
+    events.sort_unstable();
+
+    let mut active: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (x, event, index) in events {
+        let (wire, segment) = segments[index];
+        match event {
+            Event::HorizontalStart => active.entry(segment.line()).or_default().push(index),
+            Event::HorizontalEnd => {
+                let row = active.get_mut(&segment.line()).unwrap();
+                row.retain(|&other| other != index);
+                if row.is_empty() {
+                    active.remove(&segment.line());
+                }
+            }
+            Event::Vertical => {
+                let (top, bottom) = segment.span();
+                for (&y, row) in active.range(top..=bottom) {
+                    for &other in row {
+                        if segments[other].0 != wire {
+                            record(Point::new(x, y), [segments[index], segments[other]]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Finds the points shared by segments of different wires that lie along
+/// the same line.
+fn collinear_overlaps<F>(segments: &[WireSegment], record: &mut F)
+where
+    F: FnMut(Point, [WireSegment; 2]),
+{
+    let mut lines: HashMap<(bool, i64), Vec<WireSegment>> = HashMap::new();
+    for &(wire, segment) in segments {
+        let key = (segment.is_horizontal(), segment.line());
+        lines.entry(key).or_default().push((wire, segment));
+    }
+    for line in lines.values_mut() {
+        line.sort_unstable_by_key(|(_, segment)| segment.span());
+        let mut active: Vec<WireSegment> = vec![];
+        for &(wire, segment) in line.iter() {
+            let (start, end) = segment.span();
+            active.retain(|(_, other)| other.span().1 >= start);
+            for &(other_wire, other) in &active {
+                if other_wire == wire {
+                    continue;
+                }
+                for coordinate in start..=end.min(other.span().1) {
+                    let point = segment.point_at(coordinate);
+                    record(point, [(wire, segment), (other_wire, other)]);
+                }
+            }
+            active.push((wire, segment));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wires(lines: &[&str]) -> Vec<Wire> {
+        lines.iter().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_crossings() {
+        let crossings = crossings(&wires(&["R8,U5,L5,D3", "U7,R6,D4,L4"]));
+        let points: Vec<_> = crossings.iter().map(|c| c.point).collect();
+        assert_eq!(points, vec![Point::new(3, -3), Point::new(6, -5)]);
+        assert_eq!(crossings[0].steps, vec![(0, 20), (1, 20)]);
+        assert_eq!(crossings[0].distance_from_origin(), 6);
+        assert_eq!(crossings[1].combined_steps(), 30);
+    }
+
+    #[test]
+    fn test_overlaps_and_self_crossings() {
+        // the first wire crosses itself at (2, 0), and the wires run along
+        // each other from (2, 0) to (4, 0) and from (2, 0) to (2, 1)
+        let crossings = crossings(&wires(&["R4,U1,L2,D2", "D1,R2,U1,R2"]));
+        let points: Vec<_> = crossings.iter().map(|c| c.point).collect();
+        let expected = [(2, 0), (2, 1), (3, 0), (4, 0)].map(Point::from);
+        assert_eq!(points, expected.to_vec());
+        // the first wire reaches (2, 0) after 2 steps, not the later 8
+        assert_eq!(crossings[0].steps, vec![(0, 2), (1, 4)]);
+        assert_eq!(crossings[1].steps, vec![(0, 9), (1, 3)]);
+    }
+
+    #[test]
+    fn test_many_long_wires() {
+        let million = 1_000_000;
+        let wires = vec![
+            vec![(Direction::Right, million), (Direction::Up, million)],
+            vec![(Direction::Up, million), (Direction::Right, million)],
+            vec![
+                (Direction::Left, 1),
+                (Direction::Up, million / 2),
+                (Direction::Right, 2 * million),
+            ],
+        ];
+        let wires: Vec<_> = wires
+            .into_iter()
+            .map(|moves| Wire::new(moves).unwrap())
+            .collect();
+        let crossings = crossings(&wires);
+        let at = |x: u64, y: u64| {
+            let point = Point::new(x as i64, -(y as i64));
+            crossings.iter().find(|c| c.point == point).unwrap()
+        };
+        assert_eq!(crossings.len(), 3);
+        assert_eq!(
+            at(million, million).steps,
+            vec![(0, 2 * million), (1, 2 * million)]
+        );
+        assert_eq!(
+            at(0, million / 2).steps,
+            vec![(1, million / 2), (2, million / 2 + 2)]
+        );
+        assert_eq!(at(million, million / 2).steps.len(), 2);
+    }
+
+    #[test]
+    fn test_queries() {
+        // the third wire runs up through (3, -3), where the first two cross
+        let wires = wires(&["R8,U5,L5,D3", "U7,R6,D4,L4", "D1,R3,U10"]);
+        let crossings = crossings(&wires);
+        assert_eq!(crossings.len(), 7);
+        let closest_by = |distance| closest(&crossings, distance).unwrap().point;
+        assert_eq!(closest_by(Distance::Manhattan), Point::new(3, 0));
+        assert_eq!(closest_by(Distance::Steps), Point::new(3, 0));
+
+        let shared: Vec<_> = shared_by(&crossings, 3).collect();
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].point, Point::new(3, -3));
+        assert_eq!(shared[0].combined_steps(), 20 + 20 + 8);
+
+        let pairs = pairwise(&crossings);
+        let keys: Vec<_> = pairs.keys().copied().collect();
+        assert_eq!(keys, vec![(0, 1), (0, 2), (1, 2)]);
+        let first_two = &pairs[&(0, 1)];
+        assert_eq!(
+            closest(first_two, Distance::Manhattan).unwrap().point,
+            Point::new(3, -3)
+        );
+        assert_eq!(
+            closest(first_two, Distance::Steps)
+                .unwrap()
+                .combined_steps(),
+            30
+        );
+        assert_eq!(pairs[&(0, 2)].len(), 5);
+        assert_eq!(pairs[&(1, 2)].len(), 2);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "R8,X5".parse::<Wire>().unwrap_err();
+        assert_eq!(error.to_string(), "move 1: invalid move \"X5\"");
+        assert_eq!("R8,U".parse::<Wire>().unwrap_err().index, 1);
+        assert_eq!("R8,U0".parse::<Wire>(), "R8".parse());
+        // lengths that don't fit in an i64, and wires that go out of range
+        let index = |wire: &str| wire.parse::<Wire>().unwrap_err().index;
+        assert_eq!(index("R18446744073709551615"), 0);
+        assert_eq!(index("U1,L9223372036854775808"), 1);
+        assert_eq!(index("R9223372036854775807,R1"), 1);
+        assert_eq!(index("L9223372036854775807,L1"), 1);
+        assert!("L9223372036854775807,R9223372036854775807"
+            .parse::<Wire>()
+            .is_ok());
+        let steps = "R9223372036854775807,L9223372036854775807,R9223372036854775807";
+        assert_eq!(index(steps), 2);
+    }
+}
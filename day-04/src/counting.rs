@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// The longest password that fits in a `u64`.
+pub const MAX_LENGTH: u32 = 19;
+
+/// The most run rules a password can be counted against at once.
+pub const MAX_RUNS: usize = 64;
+
+/// A group of the same digit repeated next to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Run {
+    Exactly(u8),
+    AtLeast(u8),
+}
+
+impl Run {
+    fn accepts(self, length: u8) -> bool {
+        match self {
+            Self::Exactly(k) => length == k,
+            Self::AtLeast(k) => length >= k,
+        }
+    }
+
+    pub fn length(self) -> u8 {
+        match self {
+            Self::Exactly(k) | Self::AtLeast(k) => k,
+        }
+    }
+}
+
+/// What's known about a password's digits once they've all been chosen:
+/// whether they're in order either way, and which of the runs being counted
+/// they have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Seen {
+    pub non_decreasing: bool,
+    pub non_increasing: bool,
+    met: u64,
+}
+
+impl Seen {
+    /// Whether the password has the `i`th of the runs being counted.
+    pub fn has_run(self, i: usize) -> bool {
+        self.met & 1 << i != 0
+    }
+}
+
+/// Where a count has got to after some digits: the last digit, how many
+/// times it's been repeated and what's been seen before that run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    previous: Option<u8>,
+    run: u8,
+    seen: Seen,
+}
+
+/// Counts passwords a digit at a time, keeping track of what's been seen of
+/// them so `accepts` can decide at the end which ones count.
+///
+/// Counts that no longer depend on the bound only depend on the digits left
+/// and what's been seen, so they're remembered between calls to `count_in`.
+pub struct Counter<'a, F> {
+    runs: &'a [Run],
+    accepts: F,
+    /// Runs longer than any rule cares about all look the same, so they're
+    /// only counted up to this.
+    longest_run: u8,
+    memo: HashMap<(usize, State), u64>,
+}
+
+impl<'a, F> Counter<'a, F>
+where
+    F: Fn(Seen) -> bool,
+{
+    /// # Panics
+    ///
+    /// If there are more than `MAX_RUNS` runs, or any is zero digits long.
+    pub fn new(runs: &'a [Run], accepts: F) -> Self {
+        assert!(runs.len() <= MAX_RUNS, "too many run rules");
+        assert!(
+            runs.iter().all(|run| run.length() > 0),
+            "runs must be at least one digit long"
+        );
+        let longest_run = runs
+            .iter()
+            .map(|run| run.length())
+            .max()
+            .unwrap_or(0)
+            .saturating_add(1);
+        Self {
+            runs,
+            accepts,
+            longest_run,
+            memo: HashMap::new(),
+        }
+    }
+
+    /// How many passwords `length` digits long with a value in `range` are
+    /// accepted.
+    ///
+    /// # Panics
+    ///
+    /// If `length` is more than `MAX_LENGTH`.
+    pub fn count_in(&mut self, range: RangeInclusive<u64>, length: u32) -> u64 {
+        assert!(
+            length <= MAX_LENGTH,
+            "passwords can be at most {} digits",
+            MAX_LENGTH
+        );
+        let (start, end) = (*range.start(), (*range.end()).min(largest_password(length)));
+        if start > end {
+            return 0;
+        }
+        let below_start = match start.checked_sub(1) {
+            Some(before) => self.count_up_to(before, length),
+            None => 0,
+        };
+        self.count_up_to(end, length) - below_start
+    }
+
+    /// Counts the passwords from 0 up to and including `bound`.
+    fn count_up_to(&mut self, bound: u64, length: u32) -> u64 {
+        let digits = digits(bound, length);
+        let start = State {
+            previous: None,
+            run: 0,
+            seen: Seen {
+                non_decreasing: true,
+                non_increasing: true,
+                met: 0,
+            },
+        };
+        self.count_from(&digits, start, true)
+    }
+
+    /// Counts the ways of finishing a password whose remaining digits are
+    /// `bound.len()` long. While `tight` the digits so far match the bound,
+    /// so the next one can't be larger than the bound's.
+    fn count_from(&mut self, bound: &[u8], state: State, tight: bool) -> u64 {
+        let (limit, rest) = match bound.split_first() {
+            Some((&limit, rest)) => (if tight { limit } else { 9 }, rest),
+            None => return (self.accepts)(self.end_run(state)) as u64,
+        };
+        if !tight {
+            if let Some(&count) = self.memo.get(&(bound.len(), state)) {
+                return count;
+            }
+        }
+        let mut count = 0;
+        for digit in 0..=limit {
+            let next = self.step(state, digit);
+            count += self.count_from(rest, next, tight && digit == limit);
+        }
+        if !tight {
+            self.memo.insert((bound.len(), state), count);
+        }
+        count
+    }
+
+    /// The state after adding `digit`.
+    fn step(&self, state: State, digit: u8) -> State {
+        let previous = match state.previous {
+            Some(previous) => previous,
+            None => {
+                return State {
+                    previous: Some(digit),
+                    run: 1,
+                    ..state
+                }
+            }
+        };
+        if digit == previous {
+            return State {
+                run: (state.run + 1).min(self.longest_run),
+                ..state
+            };
+        }
+        let seen = self.end_run(state);
+        State {
+            previous: Some(digit),
+            run: 1,
+            seen: Seen {
+                non_decreasing: seen.non_decreasing && digit > previous,
+                non_increasing: seen.non_increasing && digit < previous,
+                ..seen
+            },
+        }
+    }
+
+    /// What's been seen once the current run of digits ends.
+    fn end_run(&self, state: State) -> Seen {
+        if state.previous.is_none() {
+            return state.seen;
+        }
+        let met = self
+            .runs
+            .iter()
+            .enumerate()
+            .filter(|(_, run)| run.accepts(state.run))
+            .fold(state.seen.met, |met, (i, _)| met | 1 << i);
+        Seen { met, ..state.seen }
+    }
+}
+
+/// The largest password `length` digits long.
+pub fn largest_password(length: u32) -> u64 {
+    10u64.checked_pow(length).map_or(u64::MAX, |n| n - 1)
+}
+
+/// The digits of `value`, most significant first, padded with zeros to
+/// `length`.
+pub fn digits(mut value: u64, length: u32) -> Vec<u8> {
+    let mut digits = vec![0; length as usize];
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 10) as u8;
+        value /= 10;
+    }
+    digits
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Which order the digits must be in, and the runs they must all have.
+    struct Rules {
+        non_decreasing: bool,
+        non_increasing: bool,
+        runs: Vec<Run>,
+    }
+
+    impl Rules {
+        fn new(non_decreasing: bool, non_increasing: bool, runs: &[Run]) -> Self {
+            Self {
+                non_decreasing,
+                non_increasing,
+                runs: runs.to_vec(),
+            }
+        }
+
+        fn count_in(&self, range: RangeInclusive<u64>, length: u32) -> u64 {
+            let mut counter = Counter::new(&self.runs, |seen| {
+                (!self.non_decreasing || seen.non_decreasing)
+                    && (!self.non_increasing || seen.non_increasing)
+                    && (0..self.runs.len()).all(|i| seen.has_run(i))
+            });
+            counter.count_in(range, length)
+        }
+    }
+
+    /// Checks a password the slow way, by looking at its digits as text.
+    fn brute_force(rules: &Rules, password: u64, length: u32) -> bool {
+        let text = format!("{:0width$}", password, width = length as usize);
+        let bytes = text.as_bytes();
+        let in_order = bytes.windows(2).all(|pair| {
+            (!rules.non_decreasing || pair[0] <= pair[1])
+                && (!rules.non_increasing || pair[0] >= pair[1])
+        });
+        let mut runs = vec![];
+        let mut start = 0;
+        for i in 1..=bytes.len() {
+            if i == bytes.len() || bytes[i] != bytes[start] {
+                runs.push((i - start) as u8);
+                start = i;
+            }
+        }
+        let has_runs = rules
+            .runs
+            .iter()
+            .all(|&rule| runs.iter().any(|&length| rule.accepts(length)));
+        in_order && has_runs
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        use Run::*;
+        let all_rules = vec![
+            Rules::new(false, false, &[]),
+            Rules::new(true, false, &[]),
+            Rules::new(false, true, &[AtLeast(2)]),
+            Rules::new(true, false, &[Exactly(2)]),
+            Rules::new(false, false, &[Exactly(3)]),
+            Rules::new(false, false, &[AtLeast(3), Exactly(1)]),
+            Rules::new(true, false, &[Exactly(2), Exactly(3)]),
+            Rules::new(true, true, &[AtLeast(2)]),
+        ];
+        let ranges = [(0, 99_999), (1_234, 56_789), (40_000, 40_000), (7, 3)];
+        for rules in &all_rules {
+            for &(start, end) in &ranges {
+                for length in 1..=5 {
+                    let largest = 10u64.pow(length) - 1;
+                    let expected = (start..=end.min(largest))
+                        .filter(|&password| brute_force(rules, password, length))
+                        .count() as u64;
+                    let counted = rules.count_in(start..=end, length);
+                    assert_eq!(
+                        counted,
+                        expected,
+                        "{:?} {:?} {}..={} {}",
+                        (rules.non_decreasing, rules.non_increasing),
+                        rules.runs,
+                        start,
+                        end,
+                        length
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_passwords() {
+        let all = 0..=u64::MAX;
+        // choosing n non-decreasing digits is choosing a multiset of size n
+        // from 10 digits, so there are (n + 9) choose 9 of them
+        let non_decreasing = Rules::new(true, false, &[]);
+        assert_eq!(non_decreasing.count_in(all.clone(), 6), 5_005);
+        assert_eq!(non_decreasing.count_in(all.clone(), 12), 293_930);
+        assert_eq!(non_decreasing.count_in(all.clone(), 18), 4_686_825);
+        // 12 digits can't all be different, so there's always a run of two
+        let with_run = Rules::new(true, false, &[Run::AtLeast(2)]);
+        assert_eq!(with_run.count_in(all.clone(), 12), 293_930);
+        let everything = Rules::new(false, false, &[]);
+        assert_eq!(everything.count_in(all.clone(), 19), 10u64.pow(19));
+        assert_eq!(everything.count_in(5..=u64::MAX, 19), 10u64.pow(19) - 5);
+        assert_eq!(everything.count_in(all, 0), 1);
+    }
+
+    #[test]
+    fn test_memo_shared_between_counts() {
+        let runs = [Run::Exactly(2)];
+        let mut counter = Counter::new(&runs, |seen| seen.non_decreasing && seen.has_run(0));
+        let first = counter.count_in(0..=999_999, 6);
+        assert_eq!(counter.count_in(123_257..=647_015, 6), 1515);
+        assert_eq!(counter.count_in(0..=999_999, 6), first);
+    }
+}
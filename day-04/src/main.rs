@@ -1,77 +1,87 @@
-const RANGE_START: u32 = 123257;
-const RANGE_END: u32 = 647015;
+mod counting;
+mod rule;
 
+use rule::Rule;
+use std::env;
+
+const RANGE_START: u64 = 123257;
+const RANGE_END: u64 = 647015;
+const PASSWORD_LENGTH: u32 = 6;
+
+// Pass a length as the first argument to also count every password that
+// long under each part's rules with the digits in different orders, followed
+// by any passwords to check against both parts.
 fn main() {
     println!("Part one: {}", solve_part_one());
     println!("Part two: {}", solve_part_two());
+    let mut args = env::args().skip(1);
+    if let Some(length) = args.next() {
+        let length = length.parse().expect("Invalid password length");
+        print_counts(length);
+    }
+    for password in args {
+        check_password(&password);
+    }
 }
 
-fn solve_part_one() -> u32 {
-    (RANGE_START..=RANGE_END)
-        .filter(|&val| has_matching_adjacent_digits(val))
-        .filter(|&val| has_nondecreasing_digits(val))
-        .count() as u32
+/// Part one's rules apart from the order of the digits.
+fn part_one_runs() -> Rule {
+    Rule::RunOfAtLeast(2)
 }
 
-fn solve_part_two() -> u32 {
-    (RANGE_START..=RANGE_END)
-        .filter(|&val| has_nondecreasing_digits(val))
-        .filter(|&val| has_two_matching_adacent_digits(val))
-        .count() as u32
+fn part_two_runs() -> Rule {
+    Rule::RunOfExactly(2)
 }
 
-fn has_matching_adjacent_digits(mut value: u32) -> bool {
-    let mut prev = value % 10;
-    value /= 10;
-    while value > 0 {
-        let current = value % 10;
-        if current == prev {
-            return true;
-        }
-        prev = current;
-        value /= 10;
-    }
-    false
+fn solve_part_one() -> usize {
+    let rule = part_one_runs().and(Rule::NonDecreasing);
+    rule.passwords(RANGE_START..=RANGE_END, PASSWORD_LENGTH)
+        .unwrap()
+        .count()
 }
 
-fn has_two_matching_adacent_digits(mut value: u32) -> bool {
-    let mut count = 1;
-    let mut digit = value % 10;
-    value /= 10;
-    while value > 0 {
-        let current = value % 10;
-        if count == 2 && current != digit {
-            return true;
-        }
-        if current == digit {
-            count += 1;
-        } else {
-            count = 1;
-            digit = current;
-        }
-        value /= 10;
+fn solve_part_two() -> usize {
+    let rule = part_two_runs().and(Rule::NonDecreasing);
+    rule.passwords(RANGE_START..=RANGE_END, PASSWORD_LENGTH)
+        .unwrap()
+        .count()
+}
+
+fn print_counts(length: u32) {
+    let either_order = Rule::NonDecreasing.or(Rule::NonIncreasing);
+    for (part, runs) in [("one", part_one_runs()), ("two", part_two_runs())] {
+        let count = |order: Rule| {
+            let rule = runs.clone().and(order);
+            rule.count_in(0..=u64::MAX, length).unwrap()
+        };
+        println!(
+            "Part {} rules, {} digits: {} non-decreasing, {} non-increasing, {} either way, {} neither",
+            part,
+            length,
+            count(Rule::NonDecreasing),
+            count(Rule::NonIncreasing),
+            count(either_order.clone()),
+            count(!either_order.clone())
+        );
     }
-    count == 2
 }
 
-fn has_nondecreasing_digits(mut value: u32) -> bool {
-    let mut prev_digit = value % 10;
-    value /= 10;
-    while value > 0 {
-        if value % 10 > prev_digit {
-            return false;
+fn check_password(password: &str) {
+    let value = password.parse().expect("Invalid password");
+    let length = password.len() as u32;
+    for (part, runs) in [("one", part_one_runs()), ("two", part_two_runs())] {
+        match runs.and(Rule::NonDecreasing).explain(value, length) {
+            None => println!("{} follows part {}'s rules", password, part),
+            Some(reason) => println!("{} breaks part {}'s rules: {}", password, part, reason),
         }
-        prev_digit = value % 10;
-        value /= 10;
     }
-    true
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    const PART_ONE_SOLUTION: u32 = 2220;
-    const PART_TWO_SOLUTION: u32 = 1515;
+    const PART_ONE_SOLUTION: usize = 2220;
+    const PART_TWO_SOLUTION: usize = 1515;
 
     #[test]
     fn part_one() {
@@ -83,6 +93,69 @@ mod test {
         assert_eq!(solve_part_two(), PART_TWO_SOLUTION);
     }
 
+    // The original checks, kept to make sure the counts agree with trying
+    // every password in the range.
+    fn has_matching_adjacent_digits(mut value: u32) -> bool {
+        let mut prev = value % 10;
+        value /= 10;
+        while value > 0 {
+            let current = value % 10;
+            if current == prev {
+                return true;
+            }
+            prev = current;
+            value /= 10;
+        }
+        false
+    }
+
+    fn has_two_matching_adacent_digits(mut value: u32) -> bool {
+        let mut count = 1;
+        let mut digit = value % 10;
+        value /= 10;
+        while value > 0 {
+            let current = value % 10;
+            if count == 2 && current != digit {
+                return true;
+            }
+            if current == digit {
+                count += 1;
+            } else {
+                count = 1;
+                digit = current;
+            }
+            value /= 10;
+        }
+        count == 2
+    }
+
+    fn has_nondecreasing_digits(mut value: u32) -> bool {
+        let mut prev_digit = value % 10;
+        value /= 10;
+        while value > 0 {
+            if value % 10 > prev_digit {
+                return false;
+            }
+            prev_digit = value % 10;
+            value /= 10;
+        }
+        true
+    }
+
+    #[test]
+    fn test_brute_force() {
+        let passwords =
+            || (RANGE_START as u32..=RANGE_END as u32).filter(|&val| has_nondecreasing_digits(val));
+        let part_one = passwords()
+            .filter(|&val| has_matching_adjacent_digits(val))
+            .count();
+        let part_two = passwords()
+            .filter(|&val| has_two_matching_adacent_digits(val))
+            .count();
+        assert_eq!(part_one, PART_ONE_SOLUTION);
+        assert_eq!(part_two, PART_TWO_SOLUTION);
+    }
+
     #[test]
     fn test_adjacent_digits() {
         assert!(has_matching_adjacent_digits(112345));
@@ -0,0 +1,443 @@
+use crate::counting::{self, Counter, Run, Seen, MAX_LENGTH};
+use std::fmt;
+use std::ops::{Not, RangeInclusive};
+
+/// Something a password's digits must do, which can be combined with other
+/// rules using `and`, `or` and `!`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// Each digit is the same as or larger than the one before it.
+    NonDecreasing,
+    /// Each digit is the same as or smaller than the one before it.
+    NonIncreasing,
+    /// Some digit is repeated exactly this many times in a row, not as part
+    /// of a longer run.
+    RunOfExactly(u8),
+    /// Some digit is repeated at least this many times in a row.
+    RunOfAtLeast(u8),
+    And(Box<Rule>, Box<Rule>),
+    Or(Box<Rule>, Box<Rule>),
+    Not(Box<Rule>),
+}
+
+impl Rule {
+    pub fn and(self, other: Rule) -> Self {
+        Self::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Rule) -> Self {
+        Self::Or(Box::new(self), Box::new(other))
+    }
+
+    fn holds(&self, digits: &[u8]) -> bool {
+        match self {
+            Self::NonDecreasing => digits.windows(2).all(|pair| pair[0] <= pair[1]),
+            Self::NonIncreasing => digits.windows(2).all(|pair| pair[0] >= pair[1]),
+            Self::RunOfExactly(k) => runs(digits).any(|run| run == *k),
+            Self::RunOfAtLeast(k) => runs(digits).any(|run| run >= *k),
+            Self::And(a, b) => a.holds(digits) && b.holds(digits),
+            Self::Or(a, b) => a.holds(digits) || b.holds(digits),
+            Self::Not(rule) => !rule.holds(digits),
+        }
+    }
+
+    /// Why `password`, written with `length` digits, breaks the rule, or
+    /// `None` if it doesn't.
+    pub fn explain(&self, password: u64, length: u32) -> Option<String> {
+        self.explain_digits(&counting::digits(password, length))
+    }
+
+    fn explain_digits(&self, digits: &[u8]) -> Option<String> {
+        if self.holds(digits) {
+            return None;
+        }
+        let explanation = match self {
+            Self::NonDecreasing => out_of_order(digits, |a, b| b < a, "smaller"),
+            Self::NonIncreasing => out_of_order(digits, |a, b| b > a, "larger"),
+            Self::RunOfExactly(k) => format!("has no run of exactly {} of the same digit", k),
+            Self::RunOfAtLeast(k) => format!("has no run of {} or more of the same digit", k),
+            // only the halves that don't hold have anything to explain
+            Self::And(a, b) => a
+                .explain_digits(digits)
+                .into_iter()
+                .chain(b.explain_digits(digits))
+                .collect::<Vec<_>>()
+                .join("; "),
+            Self::Or(a, b) => format!(
+                "{}, and {}",
+                a.explain_digits(digits)?,
+                b.explain_digits(digits)?
+            ),
+            Self::Not(rule) => format!("has {}", rule),
+        };
+        Some(explanation)
+    }
+
+    /// How many passwords `length` digits long with a value in `range`
+    /// follow the rule.
+    ///
+    /// Rather than trying each password, they're counted a digit at a time
+    /// keeping track of which of the rule's runs and orders they have, and
+    /// the rule is only checked once the digits have all been chosen.
+    ///
+    /// # Panics
+    ///
+    /// If `length` is more than `MAX_LENGTH`.
+    pub fn count_in(&self, range: RangeInclusive<u64>, length: u32) -> Result<u64, RuleError> {
+        self.check()?;
+        let runs = self.runs();
+        let count = self.counter(&runs).count_in(range, length);
+        Ok(count)
+    }
+
+    /// Makes sure every run in the rule is at least one digit long.
+    fn check(&self) -> Result<(), RuleError> {
+        match self {
+            Self::NonDecreasing | Self::NonIncreasing => Ok(()),
+            Self::RunOfExactly(0) | Self::RunOfAtLeast(0) => Err(RuleError::EmptyRun),
+            Self::RunOfExactly(_) | Self::RunOfAtLeast(_) => Ok(()),
+            Self::And(a, b) | Self::Or(a, b) => a.check().and_then(|_| b.check()),
+            Self::Not(rule) => rule.check(),
+        }
+    }
+
+    /// The different runs in the rule that a password could have. Runs
+    /// longer than `MAX_LENGTH` can't be, so they aren't counted.
+    fn runs(&self) -> Vec<Run> {
+        let mut runs = vec![];
+        self.collect_runs(&mut runs);
+        runs
+    }
+
+    fn collect_runs(&self, runs: &mut Vec<Run>) {
+        let run = match self {
+            Self::NonDecreasing | Self::NonIncreasing => return,
+            Self::RunOfExactly(k) => Run::Exactly(*k),
+            Self::RunOfAtLeast(k) => Run::AtLeast(*k),
+            Self::And(a, b) | Self::Or(a, b) => {
+                a.collect_runs(runs);
+                b.collect_runs(runs);
+                return;
+            }
+            Self::Not(rule) => return rule.collect_runs(runs),
+        };
+        if u32::from(run.length()) <= MAX_LENGTH && !runs.contains(&run) {
+            runs.push(run);
+        }
+    }
+
+    /// Counts passwords following the rule, given its `runs`.
+    fn counter<'a>(&'a self, runs: &'a [Run]) -> Counter<'a, impl Fn(Seen) -> bool + 'a> {
+        Counter::new(runs, move |seen| self.accepts(runs, seen))
+    }
+
+    /// Whether a password that's been seen to have the digits in `seen`
+    /// follows the rule.
+    fn accepts(&self, runs: &[Run], seen: Seen) -> bool {
+        let has_run = |run| {
+            runs.iter()
+                .position(|&other| other == run)
+                .is_some_and(|i| seen.has_run(i))
+        };
+        match self {
+            Self::NonDecreasing => seen.non_decreasing,
+            Self::NonIncreasing => seen.non_increasing,
+            Self::RunOfExactly(k) => has_run(Run::Exactly(*k)),
+            Self::RunOfAtLeast(k) => has_run(Run::AtLeast(*k)),
+            Self::And(a, b) => a.accepts(runs, seen) && b.accepts(runs, seen),
+            Self::Or(a, b) => a.accepts(runs, seen) || b.accepts(runs, seen),
+            Self::Not(rule) => !rule.accepts(runs, seen),
+        }
+    }
+
+    /// The passwords `length` digits long with a value in `range` that
+    /// follow the rule, from smallest to largest.
+    ///
+    /// # Panics
+    ///
+    /// If `length` is more than `MAX_LENGTH`.
+    pub fn passwords(
+        &self,
+        range: RangeInclusive<u64>,
+        length: u32,
+    ) -> Result<Passwords<'_>, RuleError> {
+        self.check()?;
+        assert!(
+            length <= MAX_LENGTH,
+            "passwords can be at most {} digits",
+            MAX_LENGTH
+        );
+        let end = (*range.end()).min(counting::largest_password(length));
+        let next = Some(*range.start()).filter(|&start| start <= end);
+        Ok(Passwords {
+            rule: self,
+            next,
+            end,
+            length,
+        })
+    }
+}
+
+/// Explains the first digit that's out of order, where `out_of_order` tells
+/// whether a digit and the one after it are.
+fn out_of_order<F>(digits: &[u8], out_of_order: F, comparison: &str) -> String
+where
+    F: Fn(u8, u8) -> bool,
+{
+    let i = (1..digits.len())
+        .find(|&i| out_of_order(digits[i - 1], digits[i]))
+        .unwrap();
+    format!(
+        "digit {} ({}) is {} than the one before it ({})",
+        i + 1,
+        digits[i],
+        comparison,
+        digits[i - 1]
+    )
+}
+
+/// The lengths of each group of repeated digits, in order.
+fn runs(digits: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    let mut start = 0;
+    (1..=digits.len()).filter_map(move |i| {
+        if i < digits.len() && digits[i] == digits[start] {
+            return None;
+        }
+        let run = i - start;
+        start = i;
+        Some(run as u8)
+    })
+}
+
+impl Not for Rule {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // combined rules inside others are bracketed so they read one way
+        let part = |rule: &Rule| match rule {
+            Self::And(..) | Self::Or(..) => format!("({})", rule),
+            _ => rule.to_string(),
+        };
+        match self {
+            Self::NonDecreasing => write!(f, "non-decreasing digits"),
+            Self::NonIncreasing => write!(f, "non-increasing digits"),
+            Self::RunOfExactly(k) => write!(f, "a run of exactly {} of the same digit", k),
+            Self::RunOfAtLeast(k) => write!(f, "a run of {} or more of the same digit", k),
+            Self::And(a, b) => write!(f, "{} and {}", part(a), part(b)),
+            Self::Or(a, b) => write!(f, "{} or {}", part(a), part(b)),
+            Self::Not(rule) => write!(f, "not {}", part(rule)),
+        }
+    }
+}
+
+/// Why a rule can't be used to count or list passwords.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    /// A run of the same digit zero times.
+    EmptyRun,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyRun => write!(f, "runs must be at least one digit long"),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// The passwords in a range that follow a rule, made by `Rule::passwords`.
+#[derive(Debug, Clone)]
+pub struct Passwords<'a> {
+    rule: &'a Rule,
+    next: Option<u64>,
+    end: u64,
+    length: u32,
+}
+
+impl Passwords<'_> {
+    fn advance_past(&mut self, password: u64) {
+        self.next = password.checked_add(1).filter(|&next| next <= self.end);
+    }
+}
+
+impl Iterator for Passwords<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while let Some(candidate) = self.next {
+            self.advance_past(candidate);
+            if self.rule.holds(&counting::digits(candidate, self.length)) {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    /// Counts the passwords left without trying each one.
+    fn count(self) -> usize {
+        match self.next {
+            Some(start) => {
+                let runs = self.rule.runs();
+                let mut counter = self.rule.counter(&runs);
+                counter.count_in(start..=self.end, self.length) as usize
+            }
+            None => 0,
+        }
+    }
+
+    /// Finds the password by searching for where the count of passwords
+    /// before it reaches `n`, rather than trying each one. The counts share
+    /// what they've worked out, so each step of the search is quick.
+    fn nth(&mut self, n: usize) -> Option<u64> {
+        let start = self.next?;
+        let runs = self.rule.runs();
+        let mut counter = self.rule.counter(&runs);
+        let length = self.length;
+        let mut count_to = |end| counter.count_in(start..=end, length);
+        let n = n as u64;
+        if count_to(self.end) <= n {
+            self.next = None;
+            return None;
+        }
+        let (mut low, mut high) = (start, self.end);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if count_to(middle) > n {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        self.advance_past(low);
+        Some(low)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rules() -> Vec<Rule> {
+        let either_order = Rule::NonDecreasing.or(Rule::NonIncreasing);
+        vec![
+            Rule::NonDecreasing.and(Rule::RunOfAtLeast(2)),
+            Rule::RunOfExactly(2).or(Rule::RunOfAtLeast(4)),
+            !either_order.clone(),
+            either_order.and(!Rule::RunOfExactly(1)),
+            !(Rule::RunOfAtLeast(2).or(!Rule::NonIncreasing)),
+        ]
+    }
+
+    #[test]
+    fn test_count_matches_iteration() {
+        for rule in rules() {
+            for length in 1..=5 {
+                for &range in &[(0, u64::MAX), (123, 45_678)] {
+                    let listed: Vec<_> =
+                        rule.passwords(range.0..=range.1, length).unwrap().collect();
+                    let counted = rule.passwords(range.0..=range.1, length).unwrap().count();
+                    assert_eq!(counted, listed.len(), "{} with {} digits", rule, length);
+                    assert!(listed.windows(2).all(|pair| pair[0] < pair[1]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_nth() {
+        for rule in rules() {
+            let listed: Vec<_> = rule.passwords(100..=9_999, 4).unwrap().collect();
+            let mut passwords = rule.passwords(100..=9_999, 4).unwrap();
+            assert_eq!(passwords.nth(1), listed.get(1).copied());
+            assert_eq!(passwords.nth(5), listed.get(7).copied());
+            assert_eq!(passwords.next(), listed.get(8).copied());
+            assert_eq!(passwords.nth(listed.len()), None);
+            assert_eq!(passwords.next(), None);
+        }
+        let rule = Rule::NonDecreasing.and(Rule::RunOfExactly(2));
+        let mut passwords = rule.passwords(0..=u64::MAX, 18).unwrap();
+        assert_eq!(passwords.next(), Some(11));
+        // after 11 that's the 1,000,002nd password
+        let password = passwords.nth(1_000_000).unwrap();
+        assert_eq!(rule.explain(password, 18), None);
+        assert_eq!(rule.count_in(0..=password, 18), Ok(1_000_002));
+        assert_eq!(rule.count_in(0..=password - 1, 18), Ok(1_000_001));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let empty = Rule::NonDecreasing.and(!Rule::RunOfAtLeast(0));
+        assert_eq!(empty.count_in(0..=99, 2), Err(RuleError::EmptyRun));
+        assert_eq!(
+            Rule::RunOfExactly(0).passwords(0..=99, 2).unwrap_err(),
+            RuleError::EmptyRun
+        );
+    }
+
+    #[test]
+    fn test_many_runs() {
+        // every run length either way, which would be far too many to count
+        // by adding up and taking away overlapping counts
+        let many = (2..=40).fold(Rule::RunOfExactly(1), |rule, k| {
+            rule.or(Rule::RunOfExactly(k))
+                .or(!Rule::RunOfAtLeast(k).and(Rule::NonIncreasing))
+        });
+        let listed = many.passwords(0..=99_999, 5).unwrap().collect::<Vec<_>>();
+        assert_eq!(listed.len(), 100_000);
+        assert_eq!(many.count_in(0..=99_999, 5), Ok(100_000));
+        let some_runs = Rule::RunOfExactly(3).or(Rule::RunOfExactly(4).and(Rule::NonDecreasing));
+        let many = (5..=60).fold(some_runs, |rule, k| rule.or(Rule::RunOfExactly(k)));
+        let listed = many.passwords(0..=99_999, 5).unwrap().collect::<Vec<_>>();
+        assert_eq!(many.count_in(0..=99_999, 5), Ok(listed.len() as u64));
+        let mut passwords = many.passwords(0..=99_999, 5).unwrap();
+        assert_eq!(passwords.nth(100), listed.get(100).copied());
+        // long runs don't need to fit in the counts' run lengths
+        assert_eq!(Rule::RunOfExactly(255).count_in(0..=99, 2), Ok(0));
+    }
+
+    #[test]
+    fn test_explain() {
+        let part_two = Rule::NonDecreasing.and(Rule::RunOfExactly(2));
+        assert_eq!(part_two.explain(112233, 6), None);
+        assert_eq!(
+            part_two.explain(123444, 6).unwrap(),
+            "has no run of exactly 2 of the same digit"
+        );
+        assert_eq!(
+            part_two.explain(223450, 6).unwrap(),
+            "digit 6 (0) is smaller than the one before it (5)"
+        );
+        assert_eq!(
+            part_two.explain(123789, 6).unwrap(),
+            "has no run of exactly 2 of the same digit"
+        );
+        assert_eq!(
+            part_two.explain(321, 6).unwrap(),
+            "digit 5 (2) is smaller than the one before it (3); \
+             has no run of exactly 2 of the same digit"
+        );
+        let either_order = Rule::NonDecreasing.or(Rule::NonIncreasing);
+        assert_eq!(
+            either_order.explain(1213, 4).unwrap(),
+            "digit 3 (1) is smaller than the one before it (2), \
+             and digit 2 (2) is larger than the one before it (1)"
+        );
+        let neither = !either_order;
+        assert_eq!(
+            neither.to_string(),
+            "not (non-decreasing digits or non-increasing digits)"
+        );
+        assert_eq!(
+            neither.explain(1111, 4).unwrap(),
+            "has non-decreasing digits or non-increasing digits"
+        );
+    }
+}
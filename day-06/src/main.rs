@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+mod orbit_map;
+
+use orbit_map::OrbitMap;
 
 const INPUT: &str = include_str!("../input.txt");
 
@@ -8,57 +10,33 @@ fn main() {
     println!("Part two: {}", solve_part_two(&orbits));
 }
 
-fn parse_input() -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    for line in INPUT.lines() {
-        let mut parts = line.split(')');
-        let object = String::from(parts.next().unwrap());
-        let orbiter = String::from(parts.next().unwrap());
-        map.insert(orbiter, object);
-    }
-    map
+fn parse_input() -> OrbitMap {
+    INPUT.parse().unwrap_or_else(|e| panic!("{}", e))
 }
 
-fn solve_part_one(orbits: &HashMap<String, String>) -> u32 {
-    let mut orbit_count = 0;
-    for object in orbits.keys() {
-        let mut o = object;
-        while o != "COM" {
-            orbit_count += 1;
-            o = &orbits[o];
-        }
-    }
-    orbit_count
+fn solve_part_one(orbits: &OrbitMap) -> usize {
+    orbits.total_orbits()
 }
 
-fn solve_part_two(orbits: &HashMap<String, String>) -> u32 {
-    let you_to_com = path_to_com(orbits, "YOU");
-    let san_to_com = path_to_com(orbits, "SAN");
-    let mut path_intersection = None;
-    let mut steps = 0;
-    for object in you_to_com {
-        steps += 1;
-        if san_to_com.contains(&object) {
-            path_intersection = Some(object);
-            break;
-        }
-    }
-    let path_intersection = path_intersection.expect("No intersection found");
-    for object in san_to_com {
-        steps += 1;
-        if object == path_intersection {
-            break;
-        }
-    }
-    steps
+fn solve_part_two(orbits: &OrbitMap) -> usize {
+    let you = orbits.id("YOU").expect("YOU isn't in the map");
+    let santa = orbits.id("SAN").expect("SAN isn't in the map");
+    orbits.transfers(you, santa).unwrap()
 }
 
-fn path_to_com(orbits: &HashMap<String, String>, object: &str) -> Vec<String> {
-    let mut path = vec![];
-    let mut orbit = &orbits[object];
-    while orbit != "COM" {
-        orbit = &orbits[orbit];
-        path.push(orbit.clone());
+#[cfg(test)]
+mod test {
+    use super::*;
+    const PART_ONE_SOLUTION: usize = 234446;
+    const PART_TWO_SOLUTION: usize = 385;
+
+    #[test]
+    fn part_one() {
+        assert_eq!(solve_part_one(&parse_input()), PART_ONE_SOLUTION);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(solve_part_two(&parse_input()), PART_TWO_SOLUTION);
     }
-    path
 }
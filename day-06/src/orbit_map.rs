@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Which object orbits which, as a tree with the one object that doesn't
+/// orbit anything at the root.
+///
+/// Names are stored once and objects referred to by `ObjectId`s, and every
+/// object's depth is worked out up front so queries don't have to walk all
+/// the way to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrbitMap {
+    names: Vec<String>,
+    ids: HashMap<String, ObjectId>,
+    parents: Vec<Option<ObjectId>>,
+    depths: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId(usize);
+
+/// How far `find_depths` has got with an object.
+#[derive(Debug, Clone, Copy)]
+enum Depth {
+    Unknown,
+    /// On the path currently being walked, so reaching it again is a cycle.
+    OnPath,
+    Known(usize),
+}
+
+impl OrbitMap {
+    /// Builds the map from `(object, orbiter)` pairs, checking that they
+    /// make a single tree.
+    pub fn new<'a, I>(orbits: I) -> Result<Self, OrbitError>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut map = Self {
+            names: vec![],
+            ids: HashMap::new(),
+            parents: vec![],
+            depths: vec![],
+        };
+        for (object, orbiter) in orbits {
+            let object = map.intern(object);
+            let orbiter = map.intern(orbiter);
+            if let Some(first) = map.parents[orbiter.0] {
+                return Err(OrbitError::MultipleParents {
+                    object: map.names[orbiter.0].clone(),
+                    first: map.names[first.0].clone(),
+                    second: map.names[object.0].clone(),
+                });
+            }
+            map.parents[orbiter.0] = Some(object);
+        }
+        let mut roots: Vec<_> = (0..map.names.len())
+            .filter(|&i| map.parents[i].is_none())
+            .map(|i| map.names[i].clone())
+            .collect();
+        if roots.len() > 1 {
+            roots.sort_unstable();
+            return Err(OrbitError::MultipleRoots(roots));
+        }
+        map.depths = map.find_depths()?;
+        if roots.is_empty() {
+            return Err(OrbitError::Empty);
+        }
+        Ok(map)
+    }
+
+    fn intern(&mut self, name: &str) -> ObjectId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = ObjectId(self.names.len());
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.parents.push(None);
+        id
+    }
+
+    /// Works out each object's depth by walking up until reaching an object
+    /// whose depth is already known, then filling in the path on the way
+    /// back down, so each object is only walked through once.
+    fn find_depths(&self) -> Result<Vec<usize>, OrbitError> {
+        let mut depths = vec![Depth::Unknown; self.names.len()];
+        for start in 0..self.names.len() {
+            let mut path = vec![];
+            let mut current = Some(ObjectId(start));
+            let mut depth = loop {
+                match current {
+                    None => break 0,
+                    Some(id) => match depths[id.0] {
+                        Depth::Known(depth) => break depth + 1,
+                        Depth::OnPath => {
+                            let object = self.names[id.0].clone();
+                            return Err(OrbitError::Cycle(object));
+                        }
+                        Depth::Unknown => {
+                            depths[id.0] = Depth::OnPath;
+                            path.push(id);
+                            current = self.parents[id.0];
+                        }
+                    },
+                }
+            };
+            for id in path.into_iter().rev() {
+                depths[id.0] = Depth::Known(depth);
+                depth += 1;
+            }
+        }
+        Ok(depths
+            .into_iter()
+            .map(|depth| match depth {
+                Depth::Known(depth) => depth,
+                _ => unreachable!("every object's depth is filled in"),
+            })
+            .collect())
+    }
+
+    pub fn id(&self, name: &str) -> Option<ObjectId> {
+        self.ids.get(name).copied()
+    }
+
+    /// The object `object` orbits, or `None` for the root.
+    pub fn parent(&self, object: ObjectId) -> Option<ObjectId> {
+        self.parents[object.0]
+    }
+
+    /// How many objects `object` orbits, directly and indirectly.
+    pub fn depth(&self, object: ObjectId) -> usize {
+        self.depths[object.0]
+    }
+
+    /// The number of direct and indirect orbits of every object.
+    pub fn total_orbits(&self) -> usize {
+        self.depths.iter().sum()
+    }
+
+    /// The deepest object that both `a` and `b` are, or orbit.
+    pub fn common_ancestor(&self, mut a: ObjectId, mut b: ObjectId) -> ObjectId {
+        while self.depth(a) > self.depth(b) {
+            a = self.parent(a).unwrap();
+        }
+        while self.depth(b) > self.depth(a) {
+            b = self.parent(b).unwrap();
+        }
+        while a != b {
+            a = self.parent(a).unwrap();
+            b = self.parent(b).unwrap();
+        }
+        a
+    }
+
+    /// How many orbits apart `a` and `b` are, moving along orbits in either
+    /// direction.
+    pub fn distance(&self, a: ObjectId, b: ObjectId) -> usize {
+        let ancestor = self.common_ancestor(a, b);
+        self.depth(a) + self.depth(b) - 2 * self.depth(ancestor)
+    }
+
+    /// The orbital transfers needed for `from` to end up orbiting the same
+    /// object as `to`, or `None` if either is the root and orbits nothing.
+    pub fn transfers(&self, from: ObjectId, to: ObjectId) -> Option<usize> {
+        Some(self.distance(self.parent(from)?, self.parent(to)?))
+    }
+}
+
+impl FromStr for OrbitMap {
+    type Err = OrbitError;
+
+    /// Parses lines like `COM)B`, meaning B orbits COM.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let orbits = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let mut names = line.trim().split(')');
+                match (names.next(), names.next(), names.next()) {
+                    (Some(object), Some(orbiter), None)
+                        if !object.is_empty() && !orbiter.is_empty() =>
+                    {
+                        Ok((object, orbiter))
+                    }
+                    _ => Err(OrbitError::Parse {
+                        line: i + 1,
+                        text: line.to_string(),
+                    }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(orbits)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrbitError {
+    Parse {
+        line: usize,
+        text: String,
+    },
+    /// The map has no objects.
+    Empty,
+    MultipleParents {
+        object: String,
+        first: String,
+        second: String,
+    },
+    /// Following orbits from this object leads back to it.
+    Cycle(String),
+    /// More than one object doesn't orbit anything, so all but one of them
+    /// must orbit something missing from the map.
+    MultipleRoots(Vec<String>),
+}
+
+impl fmt::Display for OrbitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, text } => {
+                write!(
+                    f,
+                    "line {}: expected two names around ')', not {:?}",
+                    line, text
+                )
+            }
+            Self::Empty => write!(f, "there are no orbits"),
+            Self::MultipleParents {
+                object,
+                first,
+                second,
+            } => write!(f, "{} orbits both {} and {}", object, first, second),
+            Self::Cycle(object) => write!(f, "{} ends up orbiting itself", object),
+            Self::MultipleRoots(roots) => write!(
+                f,
+                "{} don't orbit anything, so some of their parents are unknown",
+                roots.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrbitError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN\n";
+
+    #[test]
+    fn test_orbit_map() {
+        let map: OrbitMap = EXAMPLE.parse().unwrap();
+        let id = |name| map.id(name).unwrap();
+        assert_eq!(map.depth(id("COM")), 0);
+        assert_eq!(map.depth(id("L")), 7);
+        assert_eq!(map.parent(id("COM")), None);
+        // the puzzle's 42 orbits, plus those of YOU and SAN
+        assert_eq!(map.total_orbits(), 42 + 7 + 5);
+        assert_eq!(map.common_ancestor(id("H"), id("L")), id("B"));
+        assert_eq!(map.common_ancestor(id("K"), id("L")), id("K"));
+        assert_eq!(map.distance(id("YOU"), id("SAN")), 6);
+        assert_eq!(map.transfers(id("YOU"), id("SAN")), Some(4));
+        assert_eq!(map.transfers(id("COM"), id("SAN")), None);
+        assert_eq!(map.id("Z"), None);
+    }
+
+    #[test]
+    fn test_depths_in_any_order() {
+        // the deepest orbits come first, so depths have to be filled in
+        // from further down the list
+        let map: OrbitMap = "C)D\nB)C\nA)B".parse().unwrap();
+        assert_eq!(map.depth(map.id("D").unwrap()), 3);
+        assert_eq!(map.total_orbits(), 6);
+    }
+
+    #[test]
+    fn test_invalid_maps() {
+        let parse = |text: &str| text.parse::<OrbitMap>().unwrap_err();
+        assert_eq!(
+            parse("COM)A\nA)B\nB)C\nC)B"),
+            OrbitError::MultipleParents {
+                object: "B".into(),
+                first: "A".into(),
+                second: "C".into()
+            }
+        );
+        assert_eq!(parse("A)B\nB)C\nC)A"), OrbitError::Cycle("A".into()));
+        assert_eq!(parse("COM)A\nB)C\nC)B"), OrbitError::Cycle("B".into()));
+        assert_eq!(
+            parse("COM)A\nX)B\nA)C"),
+            OrbitError::MultipleRoots(vec!["COM".into(), "X".into()])
+        );
+        assert_eq!(parse("\n"), OrbitError::Empty);
+        let error = parse("COM)A\nA-B");
+        assert_eq!(
+            error.to_string(),
+            "line 2: expected two names around ')', not \"A-B\""
+        );
+        assert_eq!(
+            parse("COM)A\n)B"),
+            OrbitError::Parse {
+                line: 2,
+                text: ")B".into()
+            }
+        );
+    }
+}
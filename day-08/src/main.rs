@@ -1,72 +1,65 @@
-const INPUT: &str = include_str!("../input.txt");
+use grid::{Color, Image};
+use sif::{SpaceImage, TRANSPARENT};
+use std::env;
 
-const IMAGE_DIMENSIONS: (usize, usize) = (25, 6);
+const INPUT: &str = include_str!("../input.txt");
 
-type Layer = Vec<Vec<u8>>;
+const IMAGE_WIDTH: usize = 25;
+const IMAGE_HEIGHT: usize = 6;
 
+// Pass a path such as `image.png` as the first argument to save a picture of
+// the decoded image.
 fn main() {
-    let layers = parse_input();
-    println!("Part one: {}", solve_part_one(&layers));
-    println!("Part two:\n{}", solve_part_two(&layers));
+    let image = parse_input();
+    println!("Part one: {}", solve_part_one(&image));
+    println!("Part two: {}", solve_part_two(&image));
+    if let Some(path) = env::args().nth(1) {
+        let picture = Image::from_grid(&image.composite(TRANSPARENT), |&pixel| match pixel {
+            0 => Color::BLACK,
+            1 => Color::WHITE,
+            _ => Color::rgb(128, 128, 128),
+        });
+        picture
+            .scaled(10)
+            .save(path)
+            .expect("Failed saving picture");
+    }
 }
 
-fn parse_input() -> Vec<Layer> {
-    let mut layers = vec![];
-    let mut current_layer = vec![];
-    let mut current_row = vec![];
-    let (row_size, layer_size) = IMAGE_DIMENSIONS;
-    for c in INPUT.trim().chars() {
-        let digit = c.to_digit(10).unwrap() as u8;
-        current_row.push(digit);
-        if current_row.len() >= row_size {
-            current_layer.push(current_row.clone());
-            if current_layer.len() >= layer_size {
-                layers.push(current_layer.clone());
-                current_layer.clear();
-            }
-            current_row.clear();
-        }
-    }
-    layers
+fn parse_input() -> SpaceImage {
+    SpaceImage::decode(INPUT, IMAGE_WIDTH, IMAGE_HEIGHT).unwrap_or_else(|e| panic!("{}", e))
 }
 
-fn solve_part_one(layers: &[Layer]) -> u32 {
-    let fewest_zeroes_layer = layers
-        .iter()
-        .min_by_key(|layer| count_digit(layer, 0))
+fn solve_part_one(image: &SpaceImage) -> usize {
+    let fewest_zeroes_layer = image
+        .layer_counts()
+        .into_iter()
+        .min_by_key(|counts| counts.count(0))
         .unwrap();
-    let one_count = count_digit(fewest_zeroes_layer, 1);
-    let two_count = count_digit(fewest_zeroes_layer, 2);
-    one_count * two_count
+    fewest_zeroes_layer.count(1) * fewest_zeroes_layer.count(2)
 }
 
-fn count_digit(layer: &Layer, digit: u8) -> u32 {
-    layer.iter().flatten().filter(|&&d| d == digit).count() as u32
+/// The letters shown by the image, or the image itself if they can't be
+/// read.
+fn solve_part_two(image: &SpaceImage) -> String {
+    let decoded_image = image.composite(TRANSPARENT);
+    grid::read_letters(&decoded_image.map(|&pixel| pixel == 1))
+        .unwrap_or_else(|| format!("\n{}", decoded_image))
 }
 
-fn solve_part_two(layers: &[Layer]) -> String {
-    let last_layer = &layers[layers.len() - 1];
-    let decoded_image: Layer =
-        layers
-            .iter()
-            .rev()
-            .skip(1)
-            .fold(last_layer.clone(), |mut image, layer| {
-                for (y, row) in layer.iter().enumerate() {
-                    for (x, &pixel) in row.iter().enumerate() {
-                        if pixel != 2 {
-                            image[y][x] = pixel;
-                        }
-                    }
-                }
-                image
-            });
-    let mut output = String::new();
-    for row in decoded_image {
-        for pixel in row {
-            output.push_str(&pixel.to_string());
-        }
-        output.push('\n');
+#[cfg(test)]
+mod test {
+    use super::*;
+    const PART_ONE_SOLUTION: usize = 2356;
+    const PART_TWO_SOLUTION: &str = "PZEKB";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(solve_part_one(&parse_input()), PART_ONE_SOLUTION);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(solve_part_two(&parse_input()), PART_TWO_SOLUTION);
     }
-    output
 }
@@ -1,8 +1,6 @@
 const INPUT: &str = include_str!("../input.txt");
 
-mod intcode;
-
-use intcode::Program;
+use intcode::{Int, Program};
 
 fn main() {
     let program = Program::from_input(INPUT);
@@ -10,16 +8,14 @@ fn main() {
     println!("Part two: {}", solve_part_two(&program));
 }
 
-fn solve_part_one(program: &Program) -> i64 {
+fn solve_part_one(program: &Program) -> Int {
     let mut program = program.clone();
-    program.input(1);
-    let outputs = program.run();
+    let outputs = program.run(&[1]);
     outputs[0]
 }
 
-fn solve_part_two(program: &Program) -> i64 {
+fn solve_part_two(program: &Program) -> Int {
     let mut program = program.clone();
-    program.input(2);
-    let outputs = program.run();
+    let outputs = program.run(&[2]);
     outputs[0]
 }
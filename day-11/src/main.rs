@@ -1,50 +1,58 @@
-mod intcode;
-
+use grid::{BoundingBox, Direction, Grid, Image, Point, SparseGrid, Turn};
 use intcode::{Int, Program};
-use std::collections::HashMap;
+use std::env;
 
 const INPUT: &str = include_str!("../input.txt");
 
 type Color = Int;
-type Point = (i64, i64);
 
+// Pass a path such as `hull.png` as the first argument to save a picture of
+// the panels painted in part two.
 fn main() {
     println!("Part one: {}", solve_part_one());
-    println!("Part two:\n{}", solve_part_two());
+    let registration = paint_registration();
+    println!("Part two: {}", solve_part_two(&registration));
+    if let Some(path) = env::args().nth(1) {
+        let picture = Image::from_sparse_grid(&registration, |panel| match panel {
+            Some(1) => grid::Color::WHITE,
+            // painted black, as opposed to never visited
+            Some(_) => grid::Color::rgb(64, 64, 64),
+            None => grid::Color::BLACK,
+        });
+        picture
+            .scaled(10)
+            .save(path)
+            .expect("Failed saving picture");
+    }
 }
 
 fn solve_part_one() -> usize {
-    let mut robot = PaintRobot::new();
-    let mut panels: HashMap<Point, Color> = HashMap::new();
+    let robot = PaintRobot::new();
+    let mut panels = SparseGrid::new();
     robot.run(&mut panels);
     panels.len()
 }
 
-fn solve_part_two() -> String {
-    let mut robot = PaintRobot::new();
-    let mut panels: HashMap<Point, Color> = HashMap::new();
-    panels.insert((0, 0), 1);
-    robot.run(&mut panels);
+/// The letters painted on the hull, or the panels themselves if they can't
+/// be read.
+fn solve_part_two(panels: &SparseGrid<Color>) -> String {
+    let white_panels = panels.iter().filter(|(_, &v)| v == 1).map(|(&k, _)| k);
+    let bounds = BoundingBox::from_points(white_panels).unwrap();
+    let picture = bounds.render(|point| match panels.get(point) {
+        Some(1) => '#',
+        _ => '.',
+    });
+    let image = Grid::parse(&picture, |c| c == '#');
+    grid::read_letters(&image).unwrap_or_else(|| format!("\n{}", picture))
+}
 
-    let white_panel_keys = panels.iter().filter(|(_, &v)| v == 1).map(|(&k, _)| k);
-    let x_values = white_panel_keys.clone().map(|(x, _)| x);
-    let min_x = x_values.clone().min().unwrap();
-    let max_x = x_values.clone().max().unwrap();
-    let y_values = white_panel_keys.map(|(_, y)| y);
-    let min_y = y_values.clone().min().unwrap();
-    let max_y = y_values.clone().max().unwrap();
-    let mut result = String::new();
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let c = match panels.get(&(x, y)) {
-                Some(1) => '#',
-                _ => '.',
-            };
-            result.push(c);
-        }
-        result.push('\n');
-    }
-    result
+/// The panels painted by a robot that starts on a white panel.
+fn paint_registration() -> SparseGrid<Color> {
+    let robot = PaintRobot::new();
+    let mut panels = SparseGrid::new();
+    panels.insert(Point::ORIGIN, 1);
+    robot.run(&mut panels);
+    panels
 }
 
 #[derive(Debug, Clone)]
@@ -57,64 +65,57 @@ struct PaintRobot {
 impl PaintRobot {
     fn new() -> Self {
         Self {
-            position: (0, 0),
+            position: Point::ORIGIN,
             direction: Direction::Up,
             program: Program::from_input(INPUT),
         }
     }
 
-    fn run(&mut self, panels: &mut HashMap<Point, Color>) {
-        while !self.program.is_finished() {
-            let color = panels.entry(self.position).or_insert(0);
-            self.program.input(*color);
-            let outputs = self.program.run();
-            *color = outputs[0];
-            let turn = Turn::from_intcode(outputs[1]);
-            self.direction = turn.turn(self.direction);
-            let (x, y) = self.position;
-            self.position = match self.direction {
-                Direction::Up => (x, y - 1),
-                Direction::Down => (x, y + 1),
-                Direction::Left => (x - 1, y),
-                Direction::Right => (x + 1, y),
-            };
+    fn run(self, panels: &mut SparseGrid<Color>) {
+        let Self {
+            mut position,
+            mut direction,
+            program,
+        } = self;
+        let program = program.spawn();
+        loop {
+            let color = panels.entry(position).or_insert(0);
+            // both channels disconnect once the program halts
+            if program.input.send(*color).is_err() {
+                break;
+            }
+            match program.output.recv() {
+                Ok(new_color) => *color = new_color,
+                Err(_) => break,
+            }
+            let turn = turn_from_intcode(program.output.recv().unwrap());
+            direction = direction.turn(turn);
+            position = position.step(direction);
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+fn turn_from_intcode(intcode: Int) -> Turn {
+    match intcode {
+        0 => Turn::Left,
+        1 => Turn::Right,
+        _ => panic!("invalid intcode for turn"),
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Turn {
-    Left,
-    Right,
-}
+#[cfg(test)]
+mod test {
+    use super::*;
+    const PART_ONE_SOLUTION: usize = 2343;
+    const PART_TWO_SOLUTION: &str = "JFBERBUH";
 
-impl Turn {
-    fn from_intcode(intcode: Int) -> Self {
-        match intcode {
-            0 => Self::Left,
-            1 => Self::Right,
-            _ => panic!("invalid intcode for turn"),
-        }
+    #[test]
+    fn part_one() {
+        assert_eq!(solve_part_one(), PART_ONE_SOLUTION);
     }
 
-    fn turn(&self, current_direction: Direction) -> Direction {
-        match (current_direction, self) {
-            (Direction::Up, Turn::Left) => Direction::Left,
-            (Direction::Up, Turn::Right) => Direction::Right,
-            (Direction::Down, Turn::Left) => Direction::Right,
-            (Direction::Down, Turn::Right) => Direction::Left,
-            (Direction::Left, Turn::Left) => Direction::Down,
-            (Direction::Left, Turn::Right) => Direction::Up,
-            (Direction::Right, Turn::Left) => Direction::Up,
-            (Direction::Right, Turn::Right) => Direction::Down,
-        }
+    #[test]
+    fn part_two() {
+        assert_eq!(solve_part_two(&paint_registration()), PART_TWO_SOLUTION);
     }
 }
@@ -1,25 +1,36 @@
 const INPUT: &str = include_str!("../input.txt");
 
-mod intcode;
-
+use grid::{Color, Image, Point, SparseGrid};
 use intcode::{Int, Program};
-use std::collections::HashMap;
-use std::{cmp::Ordering, fmt};
+use std::{cmp::Ordering, env, fmt};
 
-type Tiles = HashMap<(usize, usize), Tile>;
+type Tiles = SparseGrid<Tile>;
 
+// Pass a path such as `screen.png` as the first argument to save a picture of
+// the screen before the game starts.
 fn main() {
     let program = Program::from_input(INPUT);
-    println!("{}", solve_part_one(&program));
+    let screen = starting_screen(&program);
+    println!("{}", solve_part_one(&screen));
     solve_part_two(&program);
+    if let Some(path) = env::args().nth(1) {
+        let picture = Image::from_sparse_grid(&screen, |tile| {
+            tile.map_or(Color::BLACK, |tile| tile.color())
+        });
+        picture.scaled(8).save(path).expect("Failed saving picture");
+    }
+}
+
+fn solve_part_one(tiles: &Tiles) -> usize {
+    tiles.values().filter(|&&tile| tile == Tile::Block).count()
 }
 
-fn solve_part_one(program: &Program) -> usize {
+fn starting_screen(program: &Program) -> Tiles {
     let mut program = program.clone();
     let output = program.run(&[]);
-    let mut tiles = HashMap::new();
+    let mut tiles = SparseGrid::new();
     parse_output(&mut tiles, &output);
-    tiles.values().filter(|&&tile| tile == Tile::Block).count()
+    tiles
 }
 
 fn parse_output(tiles: &mut Tiles, output: &[Int]) {
@@ -31,58 +42,48 @@ fn parse_output(tiles: &mut Tiles, output: &[Int]) {
             println!("Score: {}", id);
             continue;
         }
-        let x = x as usize;
-        let y = y as usize;
         let tile = Tile::from_intcode(id);
-        tiles.insert((x, y), tile);
+        tiles.insert(Point::new(x, y), tile);
     }
 }
 
 fn solve_part_two(program: &Program) {
     let mut program = program.clone();
     program.write_to_memory(0, 2);
-    let mut tiles = HashMap::new();
-    let mut input = 0;
-    loop {
-        let output = program.run(&[input]);
-        parse_output(&mut tiles, &output);
-        let (ball_x, _) = find_tile(&tiles, Tile::Ball);
-        let (paddle_x, _) = find_tile(&tiles, Tile::Paddle);
-        input = match ball_x.cmp(&paddle_x) {
+    let game = program.spawn();
+    let mut tiles = SparseGrid::new();
+    // the ball moves once per frame, and the game reads the joystick right
+    // before moving the paddle and then the ball, so each time the ball is
+    // drawn is the moment to send the next move (on the first frame, once
+    // the paddle has been drawn too)
+    let mut ball_moved = false;
+    while let Ok(x) = game.output.recv() {
+        let triple = [x, game.output.recv().unwrap(), game.output.recv().unwrap()];
+        parse_output(&mut tiles, &triple);
+        ball_moved |= triple[0] != -1 && Tile::from_intcode(triple[2]) == Tile::Ball;
+        if !ball_moved || tiles.find(|&tile| tile == Tile::Paddle).is_none() {
+            continue;
+        }
+        ball_moved = false;
+        let ball_x = find_tile(&tiles, Tile::Ball).x;
+        let paddle_x = find_tile(&tiles, Tile::Paddle).x;
+        let input = match ball_x.cmp(&paddle_x) {
             Ordering::Less => -1,
             Ordering::Equal => 0,
             Ordering::Greater => 1,
         };
-        if !tiles.values().any(|&tile| tile == Tile::Block) {
-            break;
-        }
+        // the game may already be over
+        let _ = game.input.send(input);
     }
+    game.join().expect("Arcade stopped before the game ended");
 }
 
-fn find_tile(tiles: &Tiles, target: Tile) -> (usize, usize) {
-    tiles
-        .iter()
-        .find_map(
-            |(&pos, &tile)| {
-                if tile == target {
-                    Some(pos)
-                } else {
-                    None
-                }
-            },
-        )
-        .unwrap()
+fn find_tile(tiles: &Tiles, target: Tile) -> Point {
+    tiles.find(|&tile| tile == target).unwrap()
 }
 
 fn print_tiles(tiles: &Tiles) {
-    let max_x = tiles.keys().map(|&(x, _)| x).max().unwrap();
-    let max_y = tiles.keys().map(|&(_, y)| y).max().unwrap();
-    for y in 0..=max_y {
-        for x in 0..=max_x {
-            print!("{}", tiles[&(x, y)]);
-        }
-        println!()
-    }
+    print!("{}", tiles.render(|tile| *tile.unwrap()));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -105,6 +106,16 @@ impl Tile {
             _ => panic!("invalid intcode"),
         }
     }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Empty => Color::BLACK,
+            Self::Wall => Color::rgb(128, 128, 128),
+            Self::Block => Color::rgb(200, 120, 40),
+            Self::Paddle => Color::WHITE,
+            Self::Ball => Color::rgb(220, 40, 40),
+        }
+    }
 }
 
 impl fmt::Display for Tile {
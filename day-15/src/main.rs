@@ -1,33 +1,38 @@
 const INPUT: &str = include_str!("../input.txt");
 
-mod intcode;
-
+use grid::{Color, Direction, Image, Point, SparseGrid};
 use intcode::{Int, Program};
-use std::collections::{HashMap, HashSet};
-use std::fmt;
-use Direction::*;
+use std::{env, fmt};
 
-type Position = (i32, i32);
+type Position = Point;
+type Map = SparseGrid<Tile>;
 
+// Pass a path such as `map.png` as the first argument to save a picture of
+// the map.
 fn main() {
     let program = Program::from_input(INPUT);
     let map = map_world(&program);
     print_map(&map);
     println!("Part one: {}", solve_part_one(&map));
     println!("Part two: {}", solve_part_two(&map));
+    if let Some(path) = env::args().nth(1) {
+        map_picture(&map).save(path).expect("Failed saving picture");
+    }
 }
 
-fn map_world(program: &Program) -> HashMap<Position, Tile> {
-    let mut map = HashMap::new();
-    let start_position = (0, 0);
+fn map_world(program: &Program) -> Map {
+    let mut map = SparseGrid::new();
+    let start_position = Point::ORIGIN;
     map.insert(start_position, Tile::Empty);
+    // each branch of the search carries its own copy of the drone's program,
+    // which `spawn`'s threads can't be cloned into, so this resumes with `run`
     let mut stack = vec![];
-    for &direction in [North, East, South, West].iter() {
+    for &direction in &Direction::ALL {
         stack.push((program.clone(), start_position, direction));
     }
     while let Some((mut program, drone_position, direction)) = stack.pop() {
-        let destination = next_position(drone_position, direction);
-        let command = direction.to_command();
+        let destination = drone_position.step(direction);
+        let command = to_command(direction);
         let output = program.run(&[command]);
         let output = output[0];
         if output == 0 {
@@ -41,9 +46,9 @@ fn map_world(program: &Program) -> HashMap<Position, Tile> {
         };
         map.insert(destination, tile);
         let drone_position = destination;
-        for &direction in [North, East, South, West].iter() {
-            let destination = next_position(drone_position, direction);
-            if map.contains_key(&destination) {
+        for &direction in &Direction::ALL {
+            let destination = drone_position.step(direction);
+            if map.contains(destination) {
                 continue;
             }
             stack.push((program.clone(), drone_position, direction));
@@ -52,107 +57,58 @@ fn map_world(program: &Program) -> HashMap<Position, Tile> {
     map
 }
 
-fn print_map(map: &HashMap<Position, Tile>) {
-    let x_values = map.keys().map(|(x, _)| x);
-    let min_x = *x_values.clone().min().unwrap();
-    let max_x = *x_values.clone().max().unwrap();
-    let y_values = map.keys().map(|(_, y)| y);
-    let min_y = *y_values.clone().min().unwrap();
-    let max_y = *y_values.clone().max().unwrap();
-    for y in (min_y..=max_y).rev() {
-        for x in min_x..=max_x {
-            if (x, y) == (0, 0) {
-                print!("S");
-                continue;
-            }
-            print!("{}", map.get(&(x, y)).unwrap_or(&Tile::Unknown));
+fn print_map(map: &Map) {
+    let rendered = map.bounds().unwrap().render(|position| {
+        if position == Point::ORIGIN {
+            return String::from("S");
         }
-        println!()
-    }
+        map.get(position).unwrap_or(&Tile::Unknown).to_string()
+    });
+    print!("{}", rendered);
 }
 
-fn next_position(current_position: Position, direction: Direction) -> Position {
-    let (x, y) = current_position;
-    match direction {
-        North => (x, y + 1),
-        East => (x + 1, y),
-        South => (x, y - 1),
-        West => (x - 1, y),
-    }
+fn map_picture(map: &Map) -> Image {
+    let picture = Image::from_bounds(map.bounds().unwrap(), |position| {
+        if position == Point::ORIGIN {
+            return Color::rgb(40, 200, 40);
+        }
+        match map.get(position).unwrap_or(&Tile::Unknown) {
+            Tile::Empty => Color::WHITE,
+            Tile::Wall => Color::rgb(96, 96, 96),
+            Tile::OxygenSystem => Color::rgb(40, 80, 220),
+            Tile::Unknown => Color::BLACK,
+        }
+    });
+    picture.scaled(8)
 }
 
-fn shortest_path_length(
-    map: &HashMap<Position, Tile>,
-    start: Position,
-    target: Position,
-) -> Option<u32> {
-    let mut steps = 0;
-    let mut queue = vec![start];
-    let mut seen = HashSet::new();
-    while !queue.is_empty() {
-        let mut next_queue = vec![];
-        for position in queue {
-            if position == target {
-                return Some(steps);
-            }
-            for &direction in &[North, East, South, West] {
-                let next_pos = next_position(position, direction);
-                if seen.contains(&next_pos) || map[&next_pos] == Tile::Wall {
-                    continue;
-                }
-                seen.insert(next_pos);
-                next_queue.push(next_pos);
-            }
-        }
-        queue = next_queue;
-        steps += 1;
-    }
-    None
+/// The positions the drone can move to from `position`.
+fn open_neighbors(map: &Map, position: Position) -> impl Iterator<Item = Position> + '_ {
+    map.neighbors(position)
+        .filter(|&(_, &tile)| tile != Tile::Wall)
+        .map(|(p, _)| p)
 }
 
-fn oxygen_system_position(map: &HashMap<Position, Tile>) -> Position {
-    map.iter()
-        .find_map(|(&position, &tile)| {
-            if tile == Tile::OxygenSystem {
-                Some(position)
-            } else {
-                None
-            }
-        })
-        .unwrap()
+fn shortest_path_length(map: &Map, start: Position, target: Position) -> Option<u32> {
+    let path = search::shortest_path(start, |&p| p == target, |&p| open_neighbors(map, p))?;
+    Some(path.len() as u32 - 1)
+}
+
+fn oxygen_system_position(map: &Map) -> Position {
+    map.find(|&tile| tile == Tile::OxygenSystem).unwrap()
 }
 
-fn solve_part_one(map: &HashMap<Position, Tile>) -> u32 {
+fn solve_part_one(map: &Map) -> u32 {
     let target = oxygen_system_position(map);
-    shortest_path_length(map, (0, 0), target).unwrap()
+    shortest_path_length(map, Point::ORIGIN, target).unwrap()
 }
 
-fn steps_to_fill(map: &HashMap<Position, Tile>, start: Position) -> u32 {
-    let mut steps = 0;
-    let mut queue = vec![start];
-    let mut seen = HashSet::new();
-    seen.insert(start);
-    while !queue.is_empty() {
-        let mut next_queue = vec![];
-        for position in queue {
-            for &direction in &[North, East, South, West] {
-                let next_pos = next_position(position, direction);
-                if seen.contains(&next_pos) || map[&next_pos] == Tile::Wall {
-                    continue;
-                }
-                seen.insert(next_pos);
-                next_queue.push(next_pos);
-            }
-        }
-        queue = next_queue;
-        if !queue.is_empty() {
-            steps += 1;
-        }
-    }
-    steps
+fn steps_to_fill(map: &Map, start: Position) -> u32 {
+    let layers = search::flood_fill(vec![start], |&p| open_neighbors(map, p));
+    layers.len() as u32 - 1
 }
 
-fn solve_part_two(map: &HashMap<Position, Tile>) -> u32 {
+fn solve_part_two(map: &Map) -> u32 {
     let start = oxygen_system_position(map);
     steps_to_fill(map, start)
 }
@@ -177,21 +133,11 @@ impl fmt::Display for Tile {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
-
-impl Direction {
-    fn to_command(&self) -> Int {
-        match self {
-            Self::North => 1,
-            Self::South => 2,
-            Self::West => 3,
-            Self::East => 4,
-        }
+fn to_command(direction: Direction) -> Int {
+    match direction {
+        Direction::Up => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+        Direction::Right => 4,
     }
 }
@@ -1,12 +1,11 @@
 const INPUT: &str = include_str!("../input.txt");
 
-mod intcode;
-
+use grid::{Direction, Grid, Point};
 use intcode::{Int, Program};
 use std::collections::HashSet;
 use std::fmt;
 
-type World = Vec<Vec<Tile>>;
+type World = Grid<Tile>;
 
 fn main() {
     let program = Program::from_input(INPUT);
@@ -15,11 +14,8 @@ fn main() {
 }
 
 fn parse_intcode_output(output: &[Int]) -> World {
-    let bytes: Vec<_> = output.iter().map(|&v| v as u8).collect();
-    bytes
-        .split(|&b| b == b'\n')
-        .map(|line| line.iter().copied().map(Tile::from_byte).collect())
-        .collect()
+    let text: String = output.iter().map(|&v| v as u8 as char).collect();
+    Grid::parse(&text, Tile::from_char)
 }
 
 fn solve_part_one(program: &Program) -> u32 {
@@ -27,31 +23,21 @@ fn solve_part_one(program: &Program) -> u32 {
     let output = program.run(&[]);
     let world = parse_intcode_output(&output);
     let intersections = find_intersections(&world);
-    intersections.iter().map(|(x, y)| x * y).sum::<usize>() as u32
+    intersections.iter().map(|p| p.x * p.y).sum::<i64>() as u32
 }
 
-fn find_intersections(world: &World) -> Vec<(usize, usize)> {
+fn find_intersections(world: &World) -> Vec<Point> {
     let mut intersections = vec![];
-    for (y, row) in world.iter().enumerate() {
-        for (x, &tile) in row.iter().enumerate() {
-            if tile != Tile::Scaffold {
-                continue;
-            }
-            let above = y
-                .checked_sub(1)
-                .and_then(|i| world.get(i))
-                .and_then(|row| row.get(x));
-            let below = world.get(y + 1).and_then(|row| row.get(x));
-            let left = world
-                .get(y)
-                .and_then(|row| x.checked_sub(1).and_then(|i| row.get(i)));
-            let right = world.get(y).and_then(|row| row.get(x + 1));
-            let is_intersection = [above, below, left, right]
-                .iter()
-                .all(|t| t.is_some() && *t.unwrap() == Tile::Scaffold);
-            if is_intersection {
-                intersections.push((x, y));
-            }
+    for (position, &tile) in world.iter() {
+        if tile != Tile::Scaffold {
+            continue;
+        }
+        let scaffold_neighbors = world
+            .neighbors(position)
+            .filter(|&(_, &t)| t == Tile::Scaffold)
+            .count();
+        if scaffold_neighbors == 4 {
+            intersections.push(position);
         }
     }
     intersections
@@ -88,10 +74,8 @@ fn find_path(world: &World) -> Vec<Movement> {
     while !positions_covered.is_superset(&scaffold_positions) {
         positions_covered.insert(position);
 
-        let forward_position = next_position(position, direction);
-        let forward_tile = forward_position
-            .and_then(|(x, y)| world.get(y).and_then(|row| row.get(x)))
-            .copied();
+        let forward_position = position.step(direction);
+        let forward_tile = world.get(forward_position).copied();
         if forward_tile == Some(Tile::Scaffold) {
             let i = path.len() - 1;
             let prev_movement = &mut path[i];
@@ -100,15 +84,13 @@ fn find_path(world: &World) -> Vec<Movement> {
             } else {
                 path.push(Movement::MoveForward(1));
             }
-            position = forward_position.unwrap();
+            position = forward_position;
             continue;
         }
 
         let left_direction = direction.turn_left();
-        let left_position = next_position(position, left_direction);
-        let left_tile = left_position
-            .and_then(|(x, y)| world.get(y).and_then(|row| row.get(x)))
-            .copied();
+        let left_position = position.step(left_direction);
+        let left_tile = world.get(left_position).copied();
         if left_tile == Some(Tile::Scaffold) {
             path.push(Movement::TurnLeft);
             direction = left_direction;
@@ -116,10 +98,8 @@ fn find_path(world: &World) -> Vec<Movement> {
         }
 
         let right_direction = direction.turn_right();
-        let right_position = next_position(position, right_direction);
-        let right_tile = right_position
-            .and_then(|(x, y)| world.get(y).and_then(|row| row.get(x)))
-            .copied();
+        let right_position = position.step(right_direction);
+        let right_tile = world.get(right_position).copied();
         if right_tile == Some(Tile::Scaffold) {
             path.push(Movement::TurnRight);
             direction = right_direction;
@@ -128,38 +108,21 @@ fn find_path(world: &World) -> Vec<Movement> {
     path
 }
 
-fn next_position(position: (usize, usize), direction: Direction) -> Option<(usize, usize)> {
-    let (x, y) = position;
-    let next_position = match direction {
-        Direction::Up => y.checked_sub(1).map(|y| (x, y)),
-        Direction::Down => Some((x, y + 1)),
-        Direction::Left => x.checked_sub(1).map(|x| (x, y)),
-        Direction::Right => Some((x + 1, y)),
-    };
-    next_position
-}
-
-fn find_robot(world: &World) -> ((usize, usize), Direction) {
-    for (y, row) in world.iter().enumerate() {
-        for (x, &tile) in row.iter().enumerate() {
-            if let Tile::Robot(direction) = tile {
-                return ((x, y), direction);
-            }
+fn find_robot(world: &World) -> (Point, Direction) {
+    for (position, &tile) in world.iter() {
+        if let Tile::Robot(direction) = tile {
+            return (position, direction);
         }
     }
     panic!("no robot found");
 }
 
-fn scaffold_positions(world: &World) -> HashSet<(usize, usize)> {
-    let mut positions = HashSet::new();
-    for (y, row) in world.iter().enumerate() {
-        for (x, &tile) in row.iter().enumerate() {
-            if tile == Tile::Scaffold {
-                positions.insert((x, y));
-            }
-        }
-    }
-    positions
+fn scaffold_positions(world: &World) -> HashSet<Point> {
+    world
+        .iter()
+        .filter(|&(_, &tile)| tile == Tile::Scaffold)
+        .map(|(position, _)| position)
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -188,43 +151,14 @@ enum Tile {
 }
 
 impl Tile {
-    fn from_byte(byte: u8) -> Self {
-        match byte {
-            b'.' => Self::Empty,
-            b'#' => Self::Scaffold,
-            b'^' => Self::Robot(Direction::Up),
-            b'>' => Self::Robot(Direction::Right),
-            b'v' => Self::Robot(Direction::Down),
-            b'<' => Self::Robot(Direction::Left),
-            _ => panic!("invalid byte"),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn turn_left(&self) -> Self {
-        match self {
-            Self::Up => Self::Left,
-            Self::Left => Self::Down,
-            Self::Down => Self::Right,
-            Self::Right => Self::Up,
-        }
-    }
-
-    fn turn_right(&self) -> Self {
-        match self {
-            Self::Up => Self::Right,
-            Self::Left => Self::Up,
-            Self::Down => Self::Left,
-            Self::Right => Self::Down,
+    fn from_char(c: char) -> Self {
+        match c {
+            '.' => Self::Empty,
+            '#' => Self::Scaffold,
+            _ => match Direction::from_arrow(c) {
+                Some(direction) => Self::Robot(direction),
+                None => panic!("invalid char"),
+            },
         }
     }
 }
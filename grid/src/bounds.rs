@@ -0,0 +1,100 @@
+use crate::Point;
+use std::fmt::{self, Write};
+
+/// The smallest rectangle containing a set of points, with both corners
+/// included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        assert!(min.x <= max.x && min.y <= max.y, "empty bounding box");
+        Self { min, max }
+    }
+
+    /// The bounds of `points`, or `None` if there aren't any.
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point>,
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut bounds = Self::new(first, first);
+        for point in points {
+            bounds.include(point);
+        }
+        Some(bounds)
+    }
+
+    /// Grows the box to contain `point`.
+    pub fn include(&mut self, point: Point) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+    }
+
+    pub fn width(&self) -> u64 {
+        (self.max.x - self.min.x) as u64 + 1
+    }
+
+    pub fn height(&self) -> u64 {
+        (self.max.y - self.min.y) as u64 + 1
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    /// Every point in the box, a row at a time from the top.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let Self { min, max } = *self;
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point::new(x, y)))
+    }
+
+    /// Draws the box a row at a time from the top, with `draw` giving what
+    /// to show at each point. Every row, including the last, ends with a
+    /// newline.
+    pub fn render<F, D>(&self, mut draw: F) -> String
+    where
+        F: FnMut(Point) -> D,
+        D: fmt::Display,
+    {
+        let mut result = String::new();
+        for y in self.min.y..=self.max.y {
+            for x in self.min.x..=self.max.x {
+                write!(result, "{}", draw(Point::new(x, y))).unwrap();
+            }
+            result.push('\n');
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_points() {
+        let points = [(2, -1), (-3, 4), (0, 0)].map(Point::from);
+        let bounds = BoundingBox::from_points(points.iter().copied()).unwrap();
+        assert_eq!(bounds.min, Point::new(-3, -1));
+        assert_eq!(bounds.max, Point::new(2, 4));
+        assert_eq!((bounds.width(), bounds.height()), (6, 6));
+        assert!(points.iter().all(|&p| bounds.contains(p)));
+        assert!(!bounds.contains(Point::new(3, 0)));
+        assert_eq!(bounds.points().count(), 36);
+        assert_eq!(BoundingBox::from_points(vec![]), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let bounds = BoundingBox::new(Point::new(1, 1), Point::new(3, 2));
+        let rendered = bounds.render(|p| if p.x == p.y { '#' } else { '.' });
+        assert_eq!(rendered, "#..\n.#.\n");
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{BoundingBox, Point};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A rectangle of cells with its top left corner at the origin.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from rows of cells.
+    ///
+    /// # Panics
+    ///
+    /// If the rows aren't all the same length.
+    pub fn from_rows<R>(rows: impl IntoIterator<Item = R>) -> Self
+    where
+        R: IntoIterator<Item = T>,
+    {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+        for row in rows {
+            let before = cells.len();
+            cells.extend(row);
+            let row_width = cells.len() - before;
+            assert_eq!(
+                *width.get_or_insert(row_width),
+                row_width,
+                "row {} has a different width",
+                height
+            );
+            height += 1;
+        }
+        Self {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        }
+    }
+
+    /// Parses a grid drawn as text, one row per line, with `parse` turning
+    /// each character into a cell. Blank lines are skipped.
+    ///
+    /// # Panics
+    ///
+    /// If the lines aren't all the same length.
+    pub fn parse<F>(text: &str, mut parse: F) -> Self
+    where
+        F: FnMut(char) -> T,
+    {
+        let rows: Vec<Vec<T>> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.chars().map(&mut parse).collect())
+            .collect();
+        Self::from_rows(rows)
+    }
+
+    /// A grid of the same size with `f` applied to every cell.
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The bounds of the grid, or `None` if it has no cells.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let max = Point::new(self.width as i64 - 1, self.height as i64 - 1);
+        Some(BoundingBox::new(Point::ORIGIN, max))
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.index_of(point).is_some()
+    }
+
+    fn index_of(&self, point: Point) -> Option<usize> {
+        let in_bounds =
+            (0..self.width as i64).contains(&point.x) && (0..self.height as i64).contains(&point.y);
+        if in_bounds {
+            Some(point.y as usize * self.width + point.x as usize)
+        } else {
+            None
+        }
+    }
+
+    fn point_of(&self, index: usize) -> Point {
+        Point::new((index % self.width) as i64, (index / self.width) as i64)
+    }
+
+    /// The cell at `point`, or `None` if it's outside the grid.
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.index_of(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.index_of(point).map(move |i| &mut self.cells[i])
+    }
+
+    /// Every cell with its position, a row at a time from the top.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (self.point_of(i), cell))
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.cells.len()).map(move |i| self.point_of(i))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The position of the first cell, reading a row at a time, that matches
+    /// `predicate`.
+    pub fn find<F>(&self, predicate: F) -> Option<Point>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.cells
+            .iter()
+            .position(predicate)
+            .map(|i| self.point_of(i))
+    }
+
+    /// The orthogonal neighbors of `point` that are inside the grid.
+    pub fn neighbors(&self, point: Point) -> impl Iterator<Item = (Point, &T)> {
+        // arrays only iterate by value through the trait in edition 2018
+        IntoIterator::into_iter(point.neighbors())
+            .filter_map(move |p| self.get(p).map(|cell| (p, cell)))
+    }
+
+    /// Draws the grid a row at a time, with `draw` giving what to show for
+    /// each cell.
+    pub fn render<F, D>(&self, mut draw: F) -> String
+    where
+        F: FnMut(&T) -> D,
+        D: fmt::Display,
+    {
+        match self.bounds() {
+            Some(bounds) => bounds.render(|point| draw(&self[point])),
+            None => String::new(),
+        }
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        match self.get(point) {
+            Some(cell) => cell,
+            None => panic!("{} is outside the grid", point),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        match self.index_of(point) {
+            Some(i) => &mut self.cells[i],
+            None => panic!("{} is outside the grid", point),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(|cell| cell.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAP: &str = "#.#\n.##\n...\n";
+
+    #[test]
+    fn test_parse() {
+        let grid = Grid::parse(MAP, |c| c == '#');
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.get(Point::new(1, 1)), Some(&true));
+        assert_eq!(grid.get(Point::new(1, 2)), Some(&false));
+        assert_eq!(grid.get(Point::new(-1, 0)), None);
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.find(|&wall| wall), Some(Point::new(0, 0)));
+        let walls: Vec<_> = grid
+            .iter()
+            .filter(|(_, &wall)| wall)
+            .map(|(p, _)| p)
+            .collect();
+        assert_eq!(walls, [(0, 0), (2, 0), (1, 1), (2, 1)].map(Point::from));
+        assert_eq!(
+            grid.render(|&wall| if wall { '#' } else { '.' }),
+            "#.#\n.##\n...\n"
+        );
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let mut grid = Grid::new(3, 2, 0);
+        grid[Point::new(1, 0)] = 5;
+        let corner: Vec<_> = grid.neighbors(Point::new(0, 0)).collect();
+        assert_eq!(corner, vec![(Point::new(1, 0), &5), (Point::new(0, 1), &0)]);
+        assert_eq!(grid.neighbors(Point::new(1, 1)).count(), 3);
+        assert_eq!(grid.to_string(), "050\n000\n");
+        assert_eq!(grid.map(|&n| n > 0).find(|&b| b), Some(Point::new(1, 0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "row 1 has a different width")]
+    fn test_ragged_rows() {
+        Grid::from_rows(vec![vec![1, 2], vec![3]]);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::Vector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Turn {
+    Left,
+    Right,
+    Around,
+}
+
+impl Direction {
+    /// Every direction, clockwise from `Up`.
+    pub const ALL: [Self; 4] = [Self::Up, Self::Right, Self::Down, Self::Left];
+
+    /// Parses the first letter of a direction, `U`, `R`, `D` or `L`.
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'U' => Some(Self::Up),
+            'R' => Some(Self::Right),
+            'D' => Some(Self::Down),
+            'L' => Some(Self::Left),
+            _ => None,
+        }
+    }
+
+    /// Parses an arrow drawn as `^`, `>`, `v` or `<`.
+    pub fn from_arrow(arrow: char) -> Option<Self> {
+        match arrow {
+            '^' => Some(Self::Up),
+            '>' => Some(Self::Right),
+            'v' => Some(Self::Down),
+            '<' => Some(Self::Left),
+            _ => None,
+        }
+    }
+
+    pub fn vector(self) -> Vector {
+        match self {
+            Self::Up => Vector::new(0, -1),
+            Self::Right => Vector::new(1, 0),
+            Self::Down => Vector::new(0, 1),
+            Self::Left => Vector::new(-1, 0),
+        }
+    }
+
+    pub fn turn(self, turn: Turn) -> Self {
+        let quarters = match turn {
+            Turn::Right => 1,
+            Turn::Around => 2,
+            Turn::Left => 3,
+        };
+        let index = Self::ALL.iter().position(|&d| d == self).unwrap();
+        Self::ALL[(index + quarters) % 4]
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.turn(Turn::Left)
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.turn(Turn::Right)
+    }
+
+    pub fn reverse(self) -> Self {
+        self.turn(Turn::Around)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Direction::*;
+
+    #[test]
+    fn test_turn() {
+        assert_eq!(Up.turn_right(), Right);
+        assert_eq!(Up.turn_left(), Left);
+        assert_eq!(Left.turn_left(), Down);
+        assert_eq!(Down.turn(Turn::Around), Up);
+        for &direction in &Direction::ALL {
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.reverse().vector(), -direction.vector());
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Direction::from_letter('R'), Some(Right));
+        assert_eq!(Direction::from_letter('r'), None);
+        assert_eq!(Direction::from_arrow('v'), Some(Down));
+        assert_eq!(Direction::from_arrow('V'), None);
+    }
+}
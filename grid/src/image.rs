@@ -0,0 +1,341 @@
+use crate::{BoundingBox, Grid, Point, SparseGrid};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// How bright the color looks, from 0 for black to 255 for white.
+    pub fn luminance(self) -> u8 {
+        let weighted =
+            299 * u32::from(self.red) + 587 * u32::from(self.green) + 114 * u32::from(self.blue);
+        (weighted / 1000) as u8
+    }
+}
+
+/// The file formats an `Image` can be saved as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    /// Black and white, with pixels darker than mid-grey black.
+    Pbm,
+    /// Greyscale.
+    Pgm,
+    Ppm,
+    Png,
+}
+
+impl ExportFormat {
+    /// The format with the usual file extension of `path`, if there is one.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "pbm" => Some(Self::Pbm),
+            "pgm" => Some(Self::Pgm),
+            "ppm" => Some(Self::Ppm),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+}
+
+/// A picture in memory, usually drawn from a grid with a palette giving the
+/// color of each kind of cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, fill: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![fill; width * height],
+        }
+    }
+
+    /// One pixel per cell of `grid`, colored by `palette`.
+    pub fn from_grid<T, F>(grid: &Grid<T>, palette: F) -> Self
+    where
+        F: FnMut(&T) -> Color,
+    {
+        Self {
+            width: grid.width(),
+            height: grid.height(),
+            pixels: grid.iter().map(|(_, cell)| cell).map(palette).collect(),
+        }
+    }
+
+    /// One pixel per cell inside the bounds of `grid`, with `palette` getting
+    /// `None` for cells that haven't been set.
+    pub fn from_sparse_grid<T, F>(grid: &SparseGrid<T>, mut palette: F) -> Self
+    where
+        F: FnMut(Option<&T>) -> Color,
+    {
+        match grid.bounds() {
+            Some(bounds) => Self::from_bounds(bounds, |point| palette(grid.get(point))),
+            None => Self::new(0, 0, Color::BLACK),
+        }
+    }
+
+    /// One pixel per point in `bounds`, colored by `draw`.
+    pub fn from_bounds<F>(bounds: BoundingBox, draw: F) -> Self
+    where
+        F: FnMut(Point) -> Color,
+    {
+        Self {
+            width: bounds.width() as usize,
+            height: bounds.height() as usize,
+            pixels: bounds.points().map(draw).collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// The image with each pixel blown up into a `factor` by `factor` square,
+    /// since one pixel per cell is usually too small to see.
+    pub fn scaled(&self, factor: usize) -> Self {
+        let width = self.width * factor;
+        let height = self.height * factor;
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.pixel(x / factor, y / factor))
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn encode(&self, format: ExportFormat) -> Vec<u8> {
+        match format {
+            ExportFormat::Pbm => self.to_pbm(),
+            ExportFormat::Pgm => self.to_pgm(),
+            ExportFormat::Ppm => self.to_ppm(),
+            ExportFormat::Png => self.to_png(),
+        }
+    }
+
+    /// Writes the image in the format its extension names.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let format = ExportFormat::from_path(path).ok_or_else(|| {
+            let message = format!("{} isn't a .pbm, .pgm, .ppm or .png file", path.display());
+            io::Error::new(io::ErrorKind::InvalidInput, message)
+        })?;
+        fs::write(path, self.encode(format))
+    }
+
+    fn netpbm_header(&self, magic: &str, max_value: bool) -> Vec<u8> {
+        let mut header = format!("{}\n{} {}\n", magic, self.width, self.height);
+        if max_value {
+            header += "255\n";
+        }
+        header.into_bytes()
+    }
+
+    pub fn to_pbm(&self) -> Vec<u8> {
+        let mut bytes = self.netpbm_header("P4", false);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            // 1 is black, eight pixels to a byte with rows padded to a byte
+            for pixels in row.chunks(8) {
+                let mut byte = 0;
+                for (i, pixel) in pixels.iter().enumerate() {
+                    if pixel.luminance() < 128 {
+                        byte |= 0x80 >> i;
+                    }
+                }
+                bytes.push(byte);
+            }
+        }
+        bytes
+    }
+
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut bytes = self.netpbm_header("P5", true);
+        bytes.extend(self.pixels.iter().map(|pixel| pixel.luminance()));
+        bytes
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes = self.netpbm_header("P6", true);
+        for pixel in &self.pixels {
+            bytes.extend_from_slice(&[pixel.red, pixel.green, pixel.blue]);
+        }
+        bytes
+    }
+
+    /// Encodes the image as an 8-bit RGB PNG. The image data is stored
+    /// without compression, which keeps the encoder small.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // bit depth 8, RGB, default compression, filtering and no interlacing
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut scanlines = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            scanlines.push(0); // no filter
+            for pixel in row {
+                scanlines.extend_from_slice(&[pixel.red, pixel.green, pixel.blue]);
+            }
+        }
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let length = block.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    let (mut a, mut b) = (1, 0);
+    for &byte in bytes {
+        a = (a + u32::from(byte)) % MODULUS;
+        b = (b + a) % MODULUS;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED: Color = Color::rgb(255, 0, 0);
+
+    fn checkerboard() -> Image {
+        let grid = Grid::parse("#.\n.#\n", |c| c == '#');
+        Image::from_grid(&grid, |&lit| if lit { RED } else { Color::WHITE })
+    }
+
+    #[test]
+    fn test_netpbm() {
+        let image = checkerboard();
+        assert_eq!(image.to_pbm(), b"P4\n2 2\n\x80\x40".to_vec());
+        assert_eq!(image.to_pgm(), b"P5\n2 2\n255\n\x4c\xff\xff\x4c".to_vec());
+        let ppm = image.to_ppm();
+        assert_eq!(&ppm[..11], b"P6\n2 2\n255\n");
+        assert_eq!(&ppm[11..17], &[255, 0, 0, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_png() {
+        let png = checkerboard().scaled(3).to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 6, 0, 0, 0, 6]);
+        // the end chunk is always the same, including its checksum
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        let data = vec![7; 70_000];
+        let stream = zlib_stored(&data);
+        assert_eq!(stream.len(), 2 + 5 + 0xffff + 5 + (70_000 - 0xffff) + 4);
+        assert_eq!(
+            zlib_stored(&[]),
+            vec![0x78, 1, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_scaled_sparse() {
+        let mut grid = SparseGrid::new();
+        grid.insert(Point::new(-1, 0), true);
+        grid.insert(Point::new(1, 1), false);
+        let palette = |cell: Option<&bool>| match cell {
+            Some(true) => Color::BLACK,
+            Some(false) => RED,
+            None => Color::WHITE,
+        };
+        let image = Image::from_sparse_grid(&grid, palette).scaled(2);
+        assert_eq!((image.width(), image.height()), (6, 4));
+        assert_eq!(image.pixel(1, 1), Color::BLACK);
+        assert_eq!(image.pixel(2, 0), Color::WHITE);
+        assert_eq!(image.pixel(5, 3), RED);
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.PNG")),
+            Some(ExportFormat::Png)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("a.gif")), None);
+    }
+}
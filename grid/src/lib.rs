@@ -0,0 +1,20 @@
+//! Points, directions and grids for the puzzles that move around a 2D map.
+//!
+//! Coordinates grow rightwards and downwards, like text on a screen, so
+//! `Direction::Up` is towards smaller `y`.
+
+mod bounds;
+mod dense;
+mod direction;
+mod image;
+mod ocr;
+mod point;
+mod sparse;
+
+pub use bounds::BoundingBox;
+pub use dense::Grid;
+pub use direction::{Direction, Turn};
+pub use image::{Color, ExportFormat, Image};
+pub use ocr::read_letters;
+pub use point::{Point, Vector};
+pub use sparse::SparseGrid;
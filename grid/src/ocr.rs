@@ -0,0 +1,125 @@
+//! Reads the block capitals that some puzzles draw as their answer.
+
+use crate::Grid;
+
+/// The letters 6 pixels tall, drawn side by side with blank columns between.
+const SMALL_FONT: (&str, &str) = (
+    "ABCEFGHIJKLOPRSUYZ",
+    "\
+     .##..###...##..####.####..##..#..#.###...##.#..#.#.....##..###..###...###.#..#.#...#.####\n\
+     #..#.#..#.#..#.#....#....#..#.#..#..#.....#.#.#..#....#..#.#..#.#..#.#....#..#.#...#....#\n\
+     #..#.###..#....###..###..#....####..#.....#.##...#....#..#.#..#.#..#.#....#..#..#.#....#.\n\
+     ####.#..#.#....#....#....#.##.#..#..#.....#.#.#..#....#..#.###..###...##..#..#...#....#..\n\
+     #..#.#..#.#..#.#....#....#..#.#..#..#..#..#.#.#..#....#..#.#....#.#.....#.#..#...#...#...\n\
+     #..#.###...##..####.#.....###.#..#.###..##..#..#.####..##..#....#..#.###...##....#...####\n",
+);
+
+/// The letters 10 pixels tall.
+const LARGE_FONT: (&str, &str) = (
+    "ABCEFGHJKLNPRXZ",
+    "\
+     ..##....#####....####...######..######...####...#....#.....###..#....#..#.......#....#..#####...#####...#....#..######\n\
+     .#..#...#....#..#....#..#.......#.......#....#..#....#......#...#...#...#.......##...#..#....#..#....#..#....#.......#\n\
+     #....#..#....#..#.......#.......#.......#.......#....#......#...#..#....#.......##...#..#....#..#....#...#..#........#\n\
+     #....#..#....#..#.......#.......#.......#.......#....#......#...#.#.....#.......#.#..#..#....#..#....#...#..#.......#.\n\
+     #....#..#####...#.......#####...#####...#.......######......#...##......#.......#.#..#..#####...#####.....##.......#..\n\
+     ######..#....#..#.......#.......#.......#..###..#....#......#...##......#.......#..#.#..#.......#..#......##......#...\n\
+     #....#..#....#..#.......#.......#.......#....#..#....#......#...#.#.....#.......#..#.#..#.......#...#....#..#....#....\n\
+     #....#..#....#..#.......#.......#.......#....#..#....#..#...#...#..#....#.......#...##..#.......#...#....#..#...#.....\n\
+     #....#..#....#..#....#..#.......#.......#...##..#....#..#...#...#...#...#.......#...##..#.......#....#..#....#..#.....\n\
+     #....#..#####....####...######..#........###.#..#....#...###....#....#..######..#....#..#.......#....#..#....#..######\n",
+);
+
+/// A letter's pixels, a row at a time.
+type Glyph = Vec<Vec<bool>>;
+
+/// Reads the letters drawn in `image`, where `true` is a lit pixel, or
+/// returns `None` if any of them isn't a known letter.
+///
+/// Blank rows above and below the text are ignored, and letters are split
+/// wherever a column is blank, so the spacing between them doesn't matter.
+pub fn read_letters(image: &Grid<bool>) -> Option<String> {
+    let rows: Vec<&[bool]> = image.rows().collect();
+    let first = rows.iter().position(|row| row.contains(&true))?;
+    let last = rows.iter().rposition(|row| row.contains(&true))?;
+    let rows = &rows[first..=last];
+    let font = match rows.len() {
+        6 => font_glyphs(SMALL_FONT),
+        10 => font_glyphs(LARGE_FONT),
+        _ => return None,
+    };
+    split_glyphs(rows)
+        .iter()
+        .map(|glyph| {
+            let letter = font.iter().find(|(_, pixels)| pixels == glyph);
+            letter.map(|&(letter, _)| letter)
+        })
+        .collect()
+}
+
+fn font_glyphs((letters, picture): (&str, &str)) -> Vec<(char, Glyph)> {
+    let image = Grid::parse(picture, |c| c == '#');
+    let rows: Vec<&[bool]> = image.rows().collect();
+    letters.chars().zip(split_glyphs(&rows)).collect()
+}
+
+/// Splits rows of pixels wherever a column is blank in every row.
+fn split_glyphs(rows: &[&[bool]]) -> Vec<Glyph> {
+    let width = rows.iter().map(|row| row.len()).min().unwrap_or(0);
+    let blank_column = |x: usize| rows.iter().all(|row| !row[x]);
+    let mut glyphs = vec![];
+    let mut x = 0;
+    while x < width {
+        if blank_column(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !blank_column(x) {
+            x += 1;
+        }
+        glyphs.push(rows.iter().map(|row| row[start..x].to_vec()).collect());
+    }
+    glyphs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(picture: &str) -> Grid<bool> {
+        Grid::parse(picture, |c| c == '#')
+    }
+
+    #[test]
+    fn test_fonts() {
+        for &(letters, picture) in &[SMALL_FONT, LARGE_FONT] {
+            let image = parse(picture);
+            let rows: Vec<_> = image.rows().collect();
+            assert_eq!(split_glyphs(&rows).len(), letters.len());
+            assert_eq!(read_letters(&image).as_deref(), Some(letters));
+        }
+    }
+
+    #[test]
+    fn test_read_letters() {
+        let picture = "\
+            ......................\n\
+            .##..###...##..#...#..\n\
+            #..#.#..#.#..#.#...#..\n\
+            #..#.###..#.....#.#...\n\
+            ####.#..#.#......#....\n\
+            #..#.#..#.#..#...#....\n\
+            #..#.###...##....#....\n\
+            ......................\n";
+        assert_eq!(read_letters(&parse(picture)), Some(String::from("ABCY")));
+    }
+
+    #[test]
+    fn test_unknown_letters() {
+        assert_eq!(read_letters(&parse("#.#\n.#.\n#.#")), None);
+        assert_eq!(read_letters(&parse("....\n....")), None);
+        let smudged = ".##.\n#..#\n#..#\n####\n#..#\n##.#";
+        assert_eq!(read_letters(&parse(smudged)), None);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::Direction;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// A position on a grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// The offset between two points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Vector {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub const ORIGIN: Self = Self { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    /// The point one step away in `direction`.
+    pub fn step(self, direction: Direction) -> Self {
+        self + direction.vector()
+    }
+
+    /// The four orthogonally adjacent points, in the order of
+    /// `Direction::ALL`.
+    pub fn neighbors(self) -> [Self; 4] {
+        Direction::ALL.map(|direction| self.step(direction))
+    }
+
+    /// The eight points around this one, including diagonals, clockwise from
+    /// the top left.
+    pub fn neighbors_with_diagonals(self) -> [Self; 8] {
+        [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+        ]
+        .map(|(x, y)| self + Vector::new(x, y))
+    }
+
+    pub fn manhattan_distance(self, other: Self) -> u64 {
+        (self - other).manhattan_length()
+    }
+}
+
+impl Vector {
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan_length(self) -> u64 {
+        self.x.unsigned_abs() + self.y.unsigned_abs()
+    }
+
+    /// The shortest vector in the same direction with whole components, so
+    /// that every vector along a line from a point reduces to the same one.
+    /// The zero vector stays as it is.
+    pub fn reduced(self) -> Self {
+        let divisor = greatest_common_divisor(self.x, self.y);
+        if divisor == 0 {
+            self
+        } else {
+            Self::new(self.x / divisor, self.y / divisor)
+        }
+    }
+}
+
+fn greatest_common_divisor(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        let t = b;
+        b = a % b;
+        a = t;
+    }
+    a.abs()
+}
+
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl Add<Vector> for Point {
+    type Output = Self;
+
+    fn add(self, vector: Vector) -> Self {
+        Self::new(self.x + vector.x, self.y + vector.y)
+    }
+}
+
+impl AddAssign<Vector> for Point {
+    fn add_assign(&mut self, vector: Vector) {
+        *self = *self + vector;
+    }
+}
+
+impl Sub<Vector> for Point {
+    type Output = Self;
+
+    fn sub(self, vector: Vector) -> Self {
+        self + -vector
+    }
+}
+
+impl SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, vector: Vector) {
+        *self = *self - vector;
+    }
+}
+
+impl Sub for Point {
+    type Output = Vector;
+
+    fn sub(self, other: Self) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Add for Vector {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Neg for Vector {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Vector {
+    type Output = Self;
+
+    fn mul(self, factor: i64) -> Self {
+        Self::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl fmt::Display for Vector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}, {}>", self.x, self.y)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Point::new(3, -4);
+        let b = Point::new(-1, 2);
+        assert_eq!(a - b, Vector::new(4, -6));
+        assert_eq!(b + (a - b), a);
+        assert_eq!(a - Vector::new(3, -4), Point::ORIGIN);
+        assert_eq!(Vector::new(1, 2) * 3 - Vector::new(1, 1), Vector::new(2, 5));
+        assert_eq!(a.manhattan_distance(b), 10);
+        assert_eq!(a.manhattan_distance(Point::ORIGIN), 7);
+    }
+
+    #[test]
+    fn test_reduced() {
+        assert_eq!(Vector::new(4, -6).reduced(), Vector::new(2, -3));
+        assert_eq!(Vector::new(0, 5).reduced(), Vector::new(0, 1));
+        assert_eq!(Vector::new(-7, 0).reduced(), Vector::new(-1, 0));
+        assert_eq!(Vector::new(0, 0).reduced(), Vector::new(0, 0));
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let point = Point::new(5, 5);
+        assert_eq!(
+            point.neighbors(),
+            [(5, 4), (6, 5), (5, 6), (4, 5)].map(Point::from)
+        );
+        let around = point.neighbors_with_diagonals();
+        assert!(around.iter().all(|p| p.manhattan_distance(point) <= 2));
+        assert!(!around.contains(&point));
+    }
+}
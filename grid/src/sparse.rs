@@ -0,0 +1,168 @@
+use crate::{BoundingBox, Point};
+use std::collections::hash_map::{self, Entry, HashMap};
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Index;
+
+/// A grid that only stores the cells that have been set, for maps that grow
+/// in any direction or are mostly empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.cells.get(&point)
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.cells.get_mut(&point)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.cells.contains_key(&point)
+    }
+
+    /// Sets the cell at `point`, returning what was there before.
+    pub fn insert(&mut self, point: Point, value: T) -> Option<T> {
+        self.cells.insert(point, value)
+    }
+
+    pub fn remove(&mut self, point: Point) -> Option<T> {
+        self.cells.remove(&point)
+    }
+
+    pub fn entry(&mut self, point: Point) -> Entry<'_, Point, T> {
+        self.cells.entry(point)
+    }
+
+    /// The cells that have been set, in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<'_, Point, T> {
+        self.cells.iter()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells.keys().copied()
+    }
+
+    pub fn values(&self) -> hash_map::Values<'_, Point, T> {
+        self.cells.values()
+    }
+
+    /// The position of a cell that matches `predicate`. If several do, which
+    /// one is unspecified.
+    pub fn find<F>(&self, mut predicate: F) -> Option<Point>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.cells
+            .iter()
+            .find(|(_, value)| predicate(value))
+            .map(|(&point, _)| point)
+    }
+
+    /// The orthogonal neighbors of `point` that have been set.
+    pub fn neighbors(&self, point: Point) -> impl Iterator<Item = (Point, &T)> {
+        // arrays only iterate by value through the trait in edition 2018
+        IntoIterator::into_iter(point.neighbors())
+            .filter_map(move |p| self.get(p).map(|value| (p, value)))
+    }
+
+    /// The bounds of the cells that have been set, or `None` if there
+    /// aren't any.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.points())
+    }
+
+    /// Draws every cell inside `bounds()`, with `draw` getting `None` for
+    /// those that haven't been set.
+    pub fn render<F, D>(&self, mut draw: F) -> String
+    where
+        F: FnMut(Option<&T>) -> D,
+        D: fmt::Display,
+    {
+        match self.bounds() {
+            Some(bounds) => bounds.render(|point| draw(self.get(point))),
+            None => String::new(),
+        }
+    }
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<Point> for SparseGrid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        match self.get(point) {
+            Some(value) => value,
+            None => panic!("nothing at {}", point),
+        }
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> Self {
+        Self {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Extend<(Point, T)> for SparseGrid<T> {
+    fn extend<I: IntoIterator<Item = (Point, T)>>(&mut self, iter: I) {
+        self.cells.extend(iter);
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SparseGrid<T> {
+    type Item = (&'a Point, &'a T);
+    type IntoIter = hash_map::Iter<'a, Point, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sparse_grid() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(grid.render(|_: Option<&char>| '?'), "");
+        grid.insert(Point::new(-2, 1), 'a');
+        grid.insert(Point::new(0, -1), 'b');
+        *grid.entry(Point::new(0, 0)).or_insert('c') = 'd';
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid[Point::ORIGIN], 'd');
+        assert_eq!(grid.find(|&c| c == 'b'), Some(Point::new(0, -1)));
+        let neighbors: Vec<_> = grid.neighbors(Point::new(0, 1)).collect();
+        assert_eq!(neighbors, vec![(Point::ORIGIN, &'d')]);
+        assert_eq!(
+            grid.render(|c| c.copied().unwrap_or('.')),
+            "..b\n..d\na..\n"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{Int, Program, ProgramState};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Instructions executed per poll before yielding to other tasks.
+const STEPS_PER_POLL: usize = 10_000;
+
+/// Creates an unbounded channel of values for an `AsyncProgram`.
+pub fn channel() -> (AsyncSender, AsyncReceiver) {
+    let channel = Arc::new(Mutex::new(Channel {
+        values: VecDeque::new(),
+        waker: None,
+        senders: 1,
+    }));
+    let sender = AsyncSender {
+        channel: Arc::clone(&channel),
+    };
+    (sender, AsyncReceiver { channel })
+}
+
+#[derive(Debug)]
+struct Channel {
+    values: VecDeque<Int>,
+    waker: Option<Waker>,
+    senders: usize,
+}
+
+#[derive(Debug)]
+pub struct AsyncSender {
+    channel: Arc<Mutex<Channel>>,
+}
+
+impl AsyncSender {
+    pub fn send(&self, value: Int) {
+        let mut channel = self.channel.lock().unwrap();
+        channel.values.push_back(value);
+        if let Some(waker) = channel.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for AsyncSender {
+    fn clone(&self) -> Self {
+        self.channel.lock().unwrap().senders += 1;
+        Self {
+            channel: Arc::clone(&self.channel),
+        }
+    }
+}
+
+impl Drop for AsyncSender {
+    fn drop(&mut self) {
+        let mut channel = self.channel.lock().unwrap();
+        channel.senders -= 1;
+        if channel.senders == 0 {
+            if let Some(waker) = channel.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AsyncReceiver {
+    channel: Arc<Mutex<Channel>>,
+}
+
+impl AsyncReceiver {
+    /// Resolves to the next value, or `None` once every sender is dropped and
+    /// the channel is empty.
+    pub fn recv(&mut self) -> Recv<'_> {
+        Recv { receiver: self }
+    }
+
+    pub fn try_recv(&mut self) -> Option<Int> {
+        self.channel.lock().unwrap().values.pop_front()
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Int>> {
+        let mut channel = self.channel.lock().unwrap();
+        match channel.values.pop_front() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if channel.senders == 0 => Poll::Ready(None),
+            None => {
+                channel.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Recv<'a> {
+    receiver: &'a mut AsyncReceiver,
+}
+
+impl Future for Recv<'_> {
+    type Output = Option<Int>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// A `Program` that runs as a future, reading inputs from an `AsyncReceiver`
+/// and sending outputs to an `AsyncSender`.
+///
+/// The future is pending while the program waits for input and resolves to
+/// the program once it halts, or once its input is closed while waiting. The
+/// output sender is dropped when the future resolves.
+#[derive(Debug)]
+pub struct AsyncProgram {
+    program: Option<Program>,
+    input: AsyncReceiver,
+    output: Option<AsyncSender>,
+}
+
+impl AsyncProgram {
+    pub fn new(program: Program, input: AsyncReceiver, output: AsyncSender) -> Self {
+        Self {
+            program: Some(program),
+            input,
+            output: Some(output),
+        }
+    }
+}
+
+impl Future for AsyncProgram {
+    type Output = Program;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let program = this.program.as_mut().expect("polled after completion");
+        if program.state != ProgramState::WaitingForInput {
+            program.outputs.clear();
+            program.state = ProgramState::Running;
+        }
+        for _ in 0..STEPS_PER_POLL {
+            match program.state {
+                ProgramState::Halted => break,
+                ProgramState::WaitingForInput => match this.input.poll_recv(cx) {
+                    Poll::Ready(Some(value)) => {
+                        program.inputs.push(value);
+                        program.state = ProgramState::Running;
+                    }
+                    Poll::Ready(None) => break,
+                    Poll::Pending => return Poll::Pending,
+                },
+                _ => {
+                    program.step();
+                    if let Some(value) = program.outputs.pop() {
+                        this.output.as_ref().unwrap().send(value);
+                    }
+                }
+            }
+        }
+        if program.state == ProgramState::Running {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        this.output = None;
+        Poll::Ready(this.program.take().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Wake;
+
+    // Reads a value into address 20 and outputs it plus one, forever.
+    const INCREMENTER: &str = "3,20,1001,20,1,21,4,21,1105,1,0";
+
+    struct Task {
+        woken: AtomicBool,
+    }
+
+    impl Wake for Task {
+        fn wake(self: Arc<Self>) {
+            self.woken.store(true, Ordering::SeqCst);
+        }
+    }
+
+    // Polls every woken task in turn until all of them have completed.
+    fn run_all(futures: Vec<AsyncProgram>) -> Vec<Program> {
+        let mut tasks: Vec<_> = futures
+            .into_iter()
+            .map(|future| {
+                let task = Arc::new(Task {
+                    woken: AtomicBool::new(true),
+                });
+                (future, task, None)
+            })
+            .collect();
+        while tasks.iter().any(|(_, _, result)| result.is_none()) {
+            let mut progressed = false;
+            for (future, task, result) in tasks.iter_mut() {
+                if result.is_some() || !task.woken.swap(false, Ordering::SeqCst) {
+                    continue;
+                }
+                progressed = true;
+                let waker = Waker::from(Arc::clone(task));
+                let mut cx = Context::from_waker(&waker);
+                if let Poll::Ready(program) = Pin::new(future).poll(&mut cx) {
+                    *result = Some(program);
+                }
+            }
+            assert!(progressed, "all tasks are blocked");
+        }
+        tasks
+            .into_iter()
+            .map(|(_, _, result)| result.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_chain() {
+        let (host_sender, mut input) = channel();
+        let mut futures = vec![];
+        for _ in 0..200 {
+            let (output, next_input) = channel();
+            let program = Program::from_input(INCREMENTER);
+            futures.push(AsyncProgram::new(program, input, output));
+            input = next_input;
+        }
+        for i in 0..5 {
+            host_sender.send(i);
+        }
+        drop(host_sender);
+        let programs = run_all(futures);
+        assert!(programs
+            .iter()
+            .all(|p| p.state() == ProgramState::WaitingForInput));
+        let outputs: Vec<_> = std::iter::from_fn(|| input.try_recv()).collect();
+        assert_eq!(outputs, vec![200, 201, 202, 203, 204]);
+    }
+
+    #[test]
+    fn test_pending_until_input() {
+        let (sender, input) = channel();
+        let (output, mut receiver) = channel();
+        let mut future =
+            AsyncProgram::new(Program::from_input("3,9,4,9,4,9,99,0,0,0"), input, output);
+        let task = Arc::new(Task {
+            woken: AtomicBool::new(false),
+        });
+        let waker = Waker::from(Arc::clone(&task));
+        let mut cx = Context::from_waker(&waker);
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        sender.send(5);
+        assert!(task.woken.load(Ordering::SeqCst));
+        match Pin::new(&mut future).poll(&mut cx) {
+            Poll::Ready(program) => assert!(program.is_finished()),
+            Poll::Pending => panic!("program should have halted"),
+        }
+        assert_eq!(receiver.try_recv(), Some(5));
+        assert_eq!(receiver.try_recv(), Some(5));
+        let mut recv = receiver.recv();
+        assert_eq!(Pin::new(&mut recv).poll(&mut cx), Poll::Ready(None));
+    }
+}
@@ -0,0 +1,31 @@
+//! Prints the patch that turns one Intcode memory dump into another.
+//!
+//! Usage: `intcode-diff BEFORE AFTER > changes.patch`
+
+use intcode::{Patch, Program};
+use std::{env, fs, process};
+
+#[path = "intcode/output.rs"]
+mod output;
+
+fn main() {
+    let paths: Vec<_> = env::args().skip(1).collect();
+    if paths.len() != 2 {
+        eprintln!("usage: intcode-diff BEFORE AFTER");
+        process::exit(2);
+    }
+    let before = load(&paths[0]);
+    let after = load(&paths[1]);
+    let patch = Patch::diff(&before.memory(), &after.memory());
+    output::print(format_args!("{}", patch));
+}
+
+fn load(path: &str) -> Program {
+    let program = fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|image| Program::from_image(&image).map_err(|e| e.to_string()));
+    program.unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    })
+}
@@ -0,0 +1,32 @@
+//! Links Intcode object files into a program.
+//!
+//! Usage: `intcode-link OBJECT... > program.txt`
+
+use intcode::{link, Object};
+use std::{env, fs, process};
+
+#[path = "intcode/output.rs"]
+mod output;
+
+fn main() {
+    let paths: Vec<_> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: intcode-link OBJECT...");
+        process::exit(2);
+    }
+    let objects: Vec<Object> = paths
+        .iter()
+        .map(|path| {
+            let text = fs::read_to_string(path).unwrap_or_else(|e| fail(path, &e));
+            text.parse().unwrap_or_else(|e| fail(path, &e))
+        })
+        .collect();
+    let image = link(&objects).unwrap_or_else(|e| fail("intcode-link", &e));
+    let values: Vec<_> = image.iter().map(|value| value.to_string()).collect();
+    output::print(format_args!("{}\n", values.join(",")));
+}
+
+fn fail(context: &str, error: &dyn std::fmt::Display) -> ! {
+    eprintln!("{}: {}", context, error);
+    process::exit(1);
+}
@@ -0,0 +1,510 @@
+//! Runs and inspects Intcode programs.
+//!
+//! Usage: `intcode COMMAND [OPTIONS] PROGRAM`, see `USAGE` for the commands.
+
+use intcode::{
+    decompile, Breakpoint, Debugger, Disassembly, Expression, ImageFormat, Int, OptimizedProgram,
+    Program, ProgramState,
+};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+use std::{env, fs, panic, process};
+
+/// Like `print!`, but quietly exiting once whatever reads the output has
+/// gone away.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        output::print(format_args!($($arg)*))
+    };
+}
+
+/// Like `println!`, in the same way as `out!`.
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        output::check(writeln!(io::stdout().lock(), $($arg)*))
+    };
+}
+
+mod output;
+mod visualize;
+
+const USAGE: &str = "usage: intcode COMMAND [OPTIONS] PROGRAM
+
+commands:
+    run         run the program, printing its output
+    disasm      print the instructions and data in the program
+    decompile   print the program as pseudo-code
+    trace       run the program, printing each instruction as it executes
+    profile     run the program and report where it spent its time
+    debug       step through the program interactively
+    visualize   watch the program's memory change as it runs
+    convert     print the program in another image format
+
+options:
+    -i, --input VALUES      inputs to the program, separated by commas
+    -f, --input-file PATH   read inputs from a file
+    -a, --ascii             read inputs as text and print outputs as text
+    -l, --limit STEPS       stop tracing after this many instructions
+    -O, --optimize          run optimized instructions
+    -F, --format FORMAT     the format to convert to: text, lines, binary or listing
+
+Without --input or --input-file, inputs are read from stdin a line at a time
+as the program asks for them, or typed into the visualizer. Programs can be in
+any of the formats, which are detected automatically.";
+
+struct Options {
+    command: String,
+    path: String,
+    inputs: Inputs,
+    ascii: bool,
+    limit: Option<u64>,
+    optimize: bool,
+    format: ImageFormat,
+}
+
+enum Inputs {
+    Given(Vec<Int>),
+    Stdin,
+}
+
+impl Inputs {
+    /// The next batch of inputs, or `None` if there are no more.
+    fn next(&mut self, ascii: bool) -> Option<Vec<Int>> {
+        match self {
+            Self::Given(values) if values.is_empty() => None,
+            Self::Given(values) => Some(std::mem::take(values)),
+            Self::Stdin => {
+                let mut line = String::new();
+                match io::stdin().lock().read_line(&mut line) {
+                    Ok(0) | Err(_) => None,
+                    Ok(_) => Some(parse_inputs(&line, ascii)),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    let options = parse_options(env::args().skip(1).collect());
+    let image = fs::read(&options.path).unwrap_or_else(|e| {
+        fail(&format!("{}: {}", options.path, e));
+    });
+    let program = Program::from_image(&image).unwrap_or_else(|e| {
+        fail(&format!("{}: {}", options.path, e));
+    });
+    // the VM panics on programs it can't run, which is the program's fault
+    // rather than a bug here, so that's reported like any other error. The
+    // visualizer unwinds instead, to put the terminal back.
+    if options.command != "visualize" {
+        panic::set_hook(Box::new(|info| {
+            let payload = info.payload();
+            let message = match payload.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => payload
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .unwrap_or_else(|| String::from("unknown error")),
+            };
+            fail(&format!("the program failed: {}", message));
+        }));
+    }
+    match options.command.as_str() {
+        "run" => run(program, options),
+        "disasm" => out!("{}", Disassembly::new(&program.memory())),
+        "decompile" => out!("{}", decompile(&program.memory())),
+        "trace" => trace(program, options),
+        "profile" => profile(program, options),
+        "debug" => debug(program, options),
+        "visualize" => {
+            let inputs = match options.inputs {
+                Inputs::Given(values) => values,
+                Inputs::Stdin => vec![],
+            };
+            visualize::visualize(program, inputs, options.ascii);
+        }
+        "convert" => {
+            let bytes = options.format.write(&program.memory());
+            output::check(io::stdout().lock().write_all(&bytes));
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut args = args.into_iter();
+    let command = match args.next() {
+        Some(command) => command,
+        None => usage(),
+    };
+    let commands = [
+        "run",
+        "disasm",
+        "decompile",
+        "trace",
+        "profile",
+        "debug",
+        "visualize",
+        "convert",
+    ];
+    if !commands.contains(&command.as_str()) {
+        usage();
+    }
+    let mut path = None;
+    let mut input = None;
+    let mut input_file = None;
+    let mut ascii = false;
+    let mut limit = None;
+    let mut optimize = false;
+    let mut format = ImageFormat::Text;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "-i" | "--input" => input = Some(value()),
+            "-f" | "--input-file" => input_file = Some(value()),
+            "-a" | "--ascii" => ascii = true,
+            "-O" | "--optimize" => optimize = true,
+            "-F" | "--format" => format = value().parse().unwrap_or_else(|e: String| fail(&e)),
+            "-l" | "--limit" => {
+                let steps = value();
+                let steps = steps
+                    .parse()
+                    .unwrap_or_else(|_| fail(&format!("invalid step limit `{}`", steps)));
+                limit = Some(steps);
+            }
+            _ if arg.starts_with('-') || path.is_some() => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    let inputs = match (input, input_file) {
+        (Some(_), Some(_)) => fail("--input and --input-file can't be used together"),
+        (Some(input), None) if ascii => Inputs::Given(parse_inputs(&(input + "\n"), true)),
+        (Some(input), None) => Inputs::Given(parse_inputs(&input, false)),
+        (None, Some(file)) => {
+            let text =
+                fs::read_to_string(&file).unwrap_or_else(|e| fail(&format!("{}: {}", file, e)));
+            Inputs::Given(parse_inputs(&text, ascii))
+        }
+        (None, None) => Inputs::Stdin,
+    };
+    Options {
+        command,
+        path,
+        inputs,
+        ascii,
+        limit,
+        optimize,
+        format,
+    }
+}
+
+fn parse_inputs(text: &str, ascii: bool) -> Vec<Int> {
+    if ascii {
+        return text.bytes().map(Int::from).collect();
+    }
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|_| fail(&format!("invalid input `{}`", s)))
+        })
+        .collect()
+}
+
+fn print_outputs(outputs: &[Int], ascii: bool) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for &value in outputs {
+        let result = match value {
+            0..=127 if ascii => write!(stdout, "{}", value as u8 as char),
+            _ => writeln!(stdout, "{}", value),
+        };
+        output::check(result);
+    }
+    output::check(stdout.flush());
+}
+
+/// Runs a program with more inputs, returning its outputs and whether it
+/// has halted.
+type Runner = Box<dyn FnMut(&[Int]) -> (Vec<Int>, bool)>;
+
+fn run(program: Program, mut options: Options) {
+    let mut run_program: Runner = if options.optimize {
+        let mut program = OptimizedProgram::new(program);
+        Box::new(move |inputs| (program.run(inputs), program.is_finished()))
+    } else {
+        let mut program = program;
+        Box::new(move |inputs| (program.run(inputs), program.is_finished()))
+    };
+    let mut inputs = vec![];
+    loop {
+        let (outputs, finished) = run_program(&inputs);
+        print_outputs(&outputs, options.ascii);
+        if finished {
+            return;
+        }
+        inputs = options
+            .inputs
+            .next(options.ascii)
+            .unwrap_or_else(|| fail("the program is waiting for more input"));
+    }
+}
+
+/// Steps through `program`, supplying inputs when it waits for them and
+/// calling `visit` before each instruction. Stops early when `visit` returns
+/// false.
+fn step_through<F>(program: &mut Program, options: &mut Options, mut visit: F)
+where
+    F: FnMut(&mut Program) -> bool,
+{
+    // an input instruction is retried once its input arrives
+    let mut retrying = false;
+    loop {
+        if !retrying && !visit(program) {
+            return;
+        }
+        program.step();
+        if program.is_finished() {
+            return;
+        }
+        retrying = program.state() == ProgramState::WaitingForInput;
+        if retrying {
+            let inputs = options
+                .inputs
+                .next(options.ascii)
+                .unwrap_or_else(|| fail("the program is waiting for more input"));
+            for value in inputs {
+                program.push_input(value);
+            }
+        }
+    }
+}
+
+fn trace(mut program: Program, mut options: Options) {
+    let limit = options.limit.unwrap_or(u64::MAX);
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    step_through(&mut program, &mut options, |program| {
+        for value in program.take_outputs() {
+            output::check(writeln!(stdout, "{:28}=> {}", "", value));
+        }
+        if program.steps() >= limit {
+            return false;
+        }
+        let ip = program.instruction_pointer();
+        let instruction = match program.decode_at(ip) {
+            Some(instruction) => instruction.to_string(),
+            None => format!("data {}", program.read_from_memory(ip)),
+        };
+        let line = format!(
+            "{:>8}  {:>6}  rb={:<6} {}",
+            program.steps(),
+            ip,
+            program.relative_base(),
+            instruction
+        );
+        output::check(writeln!(stdout, "{}", line));
+        true
+    });
+    for value in program.take_outputs() {
+        output::check(writeln!(stdout, "{:28}=> {}", "", value));
+    }
+}
+
+fn profile(mut program: Program, mut options: Options) {
+    let mut counts: HashMap<usize, u64> = HashMap::new();
+    let mut output_count = 0;
+    step_through(&mut program, &mut options, |program| {
+        *counts.entry(program.instruction_pointer()).or_default() += 1;
+        output_count += program.take_outputs().len();
+        true
+    });
+    output_count += program.take_outputs().len();
+
+    let total = program.steps();
+    outln!("{} instructions executed, {} outputs", total, output_count);
+    let mut operations: HashMap<&str, u64> = HashMap::new();
+    for (&address, &count) in &counts {
+        let name = program
+            .decode_at(address)
+            .map_or("?", |instruction| instruction.operation.mnemonic());
+        *operations.entry(name).or_default() += count;
+    }
+    let percent = |count: u64| 100.0 * count as f64 / total.max(1) as f64;
+    let mut operations: Vec<_> = operations.into_iter().collect();
+    operations.sort_by_key(|&(name, count)| (std::cmp::Reverse(count), name));
+    outln!("\ninstructions:");
+    for (name, count) in operations {
+        outln!("  {:<4} {:>12} {:>6.2}%", name, count, percent(count));
+    }
+    let mut hottest: Vec<_> = counts.into_iter().collect();
+    hottest.sort_by_key(|&(address, count)| (std::cmp::Reverse(count), address));
+    outln!("\nhottest addresses:");
+    for (address, count) in hottest.into_iter().take(20) {
+        let instruction = program
+            .decode_at(address)
+            .map_or_else(|| String::from("?"), |i| i.to_string());
+        outln!(
+            "  {:>6} {:>12} {:>6.2}%  {}",
+            address,
+            count,
+            percent(count),
+            instruction
+        );
+    }
+}
+
+const DEBUG_HELP: &str = "commands:
+    s, step [N]        execute N instructions (default 1)
+    c, continue        run until a breakpoint, input or halt
+    b, break ADDRESS   break before executing the instruction at ADDRESS
+    w, watch EXPR      break when EXPR is non-zero, e.g. `mem[100] == 3`
+    i, input VALUES    queue inputs for the program
+    p, print EXPR      evaluate an expression
+    x ADDRESS [N]      show N memory cells starting at ADDRESS
+    l, list [N]        disassemble N instructions from the instruction pointer
+    r, registers       show the instruction pointer, relative base and steps
+    q, quit            exit the debugger";
+
+fn debug(program: Program, options: Options) {
+    let mut debugger = Debugger::new(program);
+    let stop_at = Rc::new(Cell::new(u64::MAX));
+    let stop = Rc::clone(&stop_at);
+    debugger.add_breakpoint(Breakpoint::Custom(Box::new(move |p| {
+        p.steps() >= stop.get()
+    })));
+    let mut inputs = match options.inputs {
+        Inputs::Given(values) => values,
+        Inputs::Stdin => vec![],
+    };
+    outln!("{}", DEBUG_HELP);
+    show_position(debugger.program());
+    let stdin = io::stdin();
+    loop {
+        out!("(intcode) ");
+        output::check(io::stdout().flush());
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let (command, argument) = match line.trim().find(' ') {
+            Some(i) => (&line.trim()[..i], line.trim()[i..].trim()),
+            None => (line.trim(), ""),
+        };
+        let program = debugger.program();
+        match command {
+            "" => continue,
+            "s" | "step" | "c" | "continue" => {
+                if program.is_finished() {
+                    outln!("the program has halted");
+                    continue;
+                }
+                let count = match (command, argument) {
+                    ("c", _) | ("continue", _) => u64::MAX,
+                    (_, "") => 1,
+                    (_, count) => match count.parse::<u64>() {
+                        Ok(count) => count,
+                        Err(_) => {
+                            outln!("invalid step count `{}`", count);
+                            continue;
+                        }
+                    },
+                };
+                stop_at.set(program.steps().saturating_add(count));
+                let outputs = debugger.run(&std::mem::take(&mut inputs));
+                stop_at.set(u64::MAX);
+                print_outputs(&outputs, options.ascii);
+                if options.ascii && !outputs.is_empty() {
+                    outln!();
+                }
+                match debugger.last_breakpoint() {
+                    Some(0) | None => {}
+                    Some(index) => outln!("hit breakpoint {}", index),
+                }
+                show_position(debugger.program());
+            }
+            "b" | "break" => match argument.parse() {
+                Ok(address) => {
+                    let index = debugger.add_breakpoint(Breakpoint::Address(address));
+                    outln!("breakpoint {} at {}", index, address);
+                }
+                Err(_) => outln!("invalid address `{}`", argument),
+            },
+            "w" | "watch" => match debugger.break_when(argument) {
+                Ok(index) => outln!("breakpoint {} when {}", index, argument),
+                Err(e) => outln!("{}", e),
+            },
+            "i" | "input" => inputs.extend(parse_inputs(argument, options.ascii)),
+            "p" | "print" => match argument.parse::<Expression>() {
+                Ok(expression) => outln!("{}", expression.evaluate(program)),
+                Err(e) => outln!("{}", e),
+            },
+            "x" => {
+                let mut words = argument.split_whitespace().map(str::parse::<usize>);
+                match (words.next(), words.next().unwrap_or(Ok(8))) {
+                    (Some(Ok(start)), Ok(count)) => {
+                        let end = start.saturating_add(count);
+                        for row in (start..end).step_by(8) {
+                            let end = row.saturating_add(8).min(end);
+                            let cells: Vec<_> = (row..end)
+                                .map(|a| format!("{:>8}", program.read_from_memory(a)))
+                                .collect();
+                            outln!("{:>6}: {}", row, cells.join(" "));
+                        }
+                    }
+                    _ => outln!("usage: x ADDRESS [N]"),
+                }
+            }
+            "l" | "list" => {
+                let count = argument.parse().unwrap_or(5);
+                let mut address = program.instruction_pointer();
+                for _ in 0..count {
+                    match program.decode_at(address) {
+                        Some(instruction) => {
+                            outln!("{:>6}  {}", address, instruction);
+                            address = instruction.next_address();
+                        }
+                        None => {
+                            outln!("{:>6}  data {}", address, program.read_from_memory(address));
+                            address += 1;
+                        }
+                    }
+                }
+            }
+            "r" | "registers" => outln!(
+                "ip={} rb={} steps={} state={:?}",
+                program.instruction_pointer(),
+                program.relative_base(),
+                program.steps(),
+                program.state()
+            ),
+            "q" | "quit" => return,
+            _ => outln!("unknown command `{}`, see the list above", command),
+        }
+    }
+}
+
+fn show_position(program: &Program) {
+    let ip = program.instruction_pointer();
+    let instruction = program
+        .decode_at(ip)
+        .map_or_else(|| String::from("invalid instruction"), |i| i.to_string());
+    let state = match program.state() {
+        ProgramState::Halted => " (halted)",
+        ProgramState::WaitingForInput => " (waiting for input)",
+        _ => "",
+    };
+    outln!("{:>6}  {}{}", ip, instruction, state);
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("intcode: {}", message);
+    process::exit(1);
+}
@@ -0,0 +1,23 @@
+//! Writing to stdout from the command-line tools, whose output is often piped
+//! into commands like `head` that stop reading early.
+
+use std::io::{self, Write};
+use std::{fmt, process};
+
+/// Writes to stdout through a lock, like `print!` but without panicking.
+pub fn print(args: fmt::Arguments<'_>) {
+    check(io::stdout().lock().write_fmt(args));
+}
+
+/// Exits quietly if stdout was closed early, and with an error if writing to
+/// it failed for any other reason.
+pub fn check(result: io::Result<()>) {
+    match result {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(e) => {
+            eprintln!("error writing output: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! A live view of a running program's memory in the terminal.
+//!
+//! The terminal is switched to unbuffered input with `stty` and drawn with
+//! ANSI escape codes, so this only works on Unix-like terminals.
+
+use intcode::{Int, Program, ProgramState};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+const COLUMNS: usize = 8;
+const MEMORY_ROWS: usize = 14;
+const LOG_LINES: usize = 6;
+/// How many instructions an access stays highlighted for.
+const FADE_STEPS: u64 = 16;
+const MAX_SPEED: u32 = 1 << 16;
+const FRAME: Duration = Duration::from_millis(33);
+
+const HELP: &str = "space pause/resume  n step  +/- speed  j/k scroll  f follow ip  q quit";
+
+const REVERSE: &str = "\x1b[7m";
+const READ: &str = "\x1b[32m";
+const WRITTEN: &str = "\x1b[31m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+const CTRL_C: u8 = 3;
+const BACKSPACE: u8 = 127;
+
+pub fn visualize(program: Program, inputs: Vec<Int>, ascii: bool) {
+    let terminal = match Terminal::enter() {
+        Some(terminal) => terminal,
+        None => crate::fail("visualize needs an interactive terminal"),
+    };
+    let keys = read_keys();
+    let mut visualizer = Visualizer::new(program, inputs, ascii);
+    loop {
+        match keys.recv_timeout(FRAME) {
+            Ok(key) => {
+                let mut keep_going = visualizer.handle_key(key);
+                while let Ok(key) = keys.try_recv() {
+                    keep_going = keep_going && visualizer.handle_key(key);
+                }
+                if !keep_going {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if !visualizer.paused {
+            for _ in 0..visualizer.speed {
+                if !visualizer.step() {
+                    break;
+                }
+            }
+        }
+        terminal.draw(&visualizer.render());
+    }
+}
+
+/// Puts the terminal into unbuffered mode on an alternate screen, restoring
+/// it when dropped.
+struct Terminal {
+    settings: String,
+}
+
+impl Terminal {
+    fn enter() -> Option<Self> {
+        let settings = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        Some(Self {
+            settings: settings.trim().to_string(),
+        })
+    }
+
+    fn draw(&self, screen: &str) {
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "\x1b[H{}\x1b[J", screen.replace('\n', "\x1b[K\r\n"));
+        let _ = stdout.flush();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        stty(&[self.settings.as_str()]);
+    }
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .ok()?;
+    if output.status.success() {
+        String::from_utf8(output.stdout).ok()
+    } else {
+        None
+    }
+}
+
+fn read_keys() -> Receiver<u8> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // read byte by byte so keys arrive as soon as they're pressed
+        let mut stdin = io::stdin();
+        let mut key = [0];
+        while let Ok(1) = stdin.read(&mut key) {
+            if sender.send(key[0]).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+struct Visualizer {
+    program: Program,
+    inputs: VecDeque<Int>,
+    ascii: bool,
+    /// The step at which each address was last read or written.
+    reads: HashMap<usize, u64>,
+    writes: HashMap<usize, u64>,
+    log: VecDeque<String>,
+    paused: bool,
+    speed: u32,
+    /// The first row of memory shown, or `None` to follow the instruction
+    /// pointer.
+    scroll: Option<usize>,
+    typed_input: String,
+}
+
+impl Visualizer {
+    fn new(program: Program, inputs: Vec<Int>, ascii: bool) -> Self {
+        Self {
+            program,
+            inputs: inputs.into(),
+            ascii,
+            reads: HashMap::new(),
+            writes: HashMap::new(),
+            log: VecDeque::new(),
+            paused: true,
+            speed: 1,
+            scroll: None,
+            typed_input: String::new(),
+        }
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.program.state() == ProgramState::WaitingForInput && self.inputs.is_empty()
+    }
+
+    /// Executes one instruction, returning false if the program can't
+    /// continue without input.
+    fn step(&mut self) -> bool {
+        if self.program.is_finished() || self.is_waiting() {
+            return false;
+        }
+        if self.program.state() == ProgramState::WaitingForInput {
+            let value = self.inputs.pop_front().unwrap();
+            self.program.push_input(value);
+        }
+        let steps = self.program.steps();
+        let relative_base = self.program.relative_base();
+        let instruction = self.program.decode_at(self.program.instruction_pointer());
+        self.program.step();
+        if self.program.state() == ProgramState::WaitingForInput {
+            return !self.inputs.is_empty();
+        }
+        if let Some(instruction) = instruction {
+            for address in instruction.read_addresses(relative_base) {
+                self.reads.insert(address, steps);
+            }
+            if let Some(address) = instruction.write_address(relative_base) {
+                self.writes.insert(address, steps);
+                if instruction.operation == intcode::Operation::Input {
+                    let value = self.program.read_from_memory(address);
+                    self.record(format!("in  {}", self.describe(value)));
+                }
+            }
+        }
+        for value in self.program.take_outputs() {
+            self.record(format!("out {}", self.describe(value)));
+        }
+        !self.program.is_finished()
+    }
+
+    fn describe(&self, value: Int) -> String {
+        match value {
+            10 if self.ascii => String::from("10 '\\n'"),
+            32..=126 if self.ascii => format!("{} '{}'", value, value as u8 as char),
+            _ => value.to_string(),
+        }
+    }
+
+    fn record(&mut self, event: String) {
+        let event = format!("{:>10}  {}", self.program.steps(), event);
+        self.log.push_back(event);
+        if self.log.len() > LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+
+    /// Handles a key press, returning false to quit.
+    fn handle_key(&mut self, key: u8) -> bool {
+        if key == CTRL_C {
+            return false;
+        }
+        if self.is_waiting() {
+            match key {
+                b'\r' | b'\n' => self.submit_input(),
+                BACKSPACE => {
+                    self.typed_input.pop();
+                }
+                b' '..=b'~' => self.typed_input.push(key as char),
+                _ => {}
+            }
+            return true;
+        }
+        match key {
+            b'q' => return false,
+            b' ' => self.paused = !self.paused,
+            b'n' | b's' => {
+                self.paused = true;
+                self.step();
+            }
+            b'+' | b'=' => self.speed = (self.speed * 2).min(MAX_SPEED),
+            b'-' => self.speed = (self.speed / 2).max(1),
+            b'j' => self.scroll = Some(self.first_row() + 1),
+            b'k' => self.scroll = Some(self.first_row().saturating_sub(1)),
+            b'J' => self.scroll = Some(self.first_row() + MEMORY_ROWS),
+            b'K' => self.scroll = Some(self.first_row().saturating_sub(MEMORY_ROWS)),
+            b'f' => self.scroll = None,
+            _ => {}
+        }
+        true
+    }
+
+    fn submit_input(&mut self) {
+        let text = std::mem::take(&mut self.typed_input);
+        if self.ascii {
+            self.inputs.extend(text.bytes().map(Int::from));
+            self.inputs.push_back(10);
+        } else {
+            let values = text
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<Int>, _>>();
+            match values {
+                Ok(values) => self.inputs.extend(values),
+                Err(_) => self.record(format!("invalid input `{}`", text)),
+            }
+        }
+    }
+
+    fn first_row(&self) -> usize {
+        self.scroll
+            .unwrap_or_else(|| (self.program.instruction_pointer() / COLUMNS).saturating_sub(3))
+    }
+
+    fn render(&self) -> String {
+        let program = &self.program;
+        let ip = program.instruction_pointer();
+        let status = if program.is_finished() {
+            "halted"
+        } else if self.is_waiting() {
+            "waiting for input"
+        } else if self.paused {
+            "paused"
+        } else {
+            "running"
+        };
+        let mut screen = format!(
+            "{}steps {}  ip {}  rb {}  {}  speed {}/frame{}\n\n",
+            BOLD,
+            program.steps(),
+            ip,
+            program.relative_base(),
+            status,
+            self.speed,
+            RESET
+        );
+
+        let instruction = program.decode_at(ip);
+        let current = ip..ip + instruction.as_ref().map_or(1, |i| i.size());
+        screen += "        ";
+        for column in 0..COLUMNS {
+            screen += &format!("{:>10}", format!("+{}", column));
+        }
+        screen.push('\n');
+        let first_row = self.first_row();
+        for row in first_row..first_row + MEMORY_ROWS {
+            screen += &format!("{:>6}  ", row * COLUMNS);
+            for address in row * COLUMNS..(row + 1) * COLUMNS {
+                let value = program.read_from_memory(address);
+                let style = if current.contains(&address) {
+                    REVERSE
+                } else {
+                    self.access_style(address)
+                };
+                screen += &format!("{}{:>10}{}", style, value, RESET);
+            }
+            screen.push('\n');
+        }
+
+        screen += &format!("\n{}instruction{}\n", BOLD, RESET);
+        match &instruction {
+            Some(instruction) => {
+                screen += &format!("{:>6}  {}", ip, instruction);
+                let values: Vec<_> = instruction
+                    .read_addresses(program.relative_base())
+                    .iter()
+                    .map(|&a| format!("[{}] = {}", a, program.read_from_memory(a)))
+                    .collect();
+                if !values.is_empty() {
+                    screen += &format!("    {}", values.join(", "));
+                }
+                screen.push('\n');
+            }
+            None => {
+                let value = program.read_from_memory(ip);
+                screen += &format!("{:>6}  invalid instruction {}\n", ip, value);
+            }
+        }
+
+        screen += &format!("\n{}relative base{}\n", BOLD, RESET);
+        let relative_base = program.relative_base();
+        let window: Vec<_> = (-2..COLUMNS as Int - 2)
+            .filter(|offset| relative_base + offset >= 0)
+            .map(|offset| {
+                let value = program.read_from_memory((relative_base + offset) as usize);
+                format!("{:>10}", format!("{:+}:{}", offset, value))
+            })
+            .collect();
+        screen += &format!("{:>6}  {}\n", relative_base, window.concat());
+
+        screen += &format!("\n{}i/o{}\n", BOLD, RESET);
+        for line in &self.log {
+            screen += &format!("{}\n", line);
+        }
+        for _ in self.log.len()..LOG_LINES {
+            screen.push('\n');
+        }
+        if self.is_waiting() {
+            screen += &format!("input> {}\n", self.typed_input);
+            screen += &format!("{}enter to send, ctrl-c to quit{}", DIM, RESET);
+        } else {
+            screen += &format!("\n{}{}{}", DIM, HELP, RESET);
+        }
+        screen
+    }
+
+    fn access_style(&self, address: usize) -> &'static str {
+        let steps = self.program.steps();
+        let recent = |accesses: &HashMap<usize, u64>| {
+            accesses
+                .get(&address)
+                .is_some_and(|&step| steps - step <= FADE_STEPS)
+        };
+        if recent(&self.writes) {
+            WRITTEN
+        } else if recent(&self.reads) {
+            READ
+        } else {
+            ""
+        }
+    }
+}
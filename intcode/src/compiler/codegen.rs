@@ -0,0 +1,397 @@
+use super::parser::{BinaryOperator, Expression, Function, Statement};
+use super::CompileError;
+use crate::Int;
+use std::collections::HashMap;
+
+type Label = usize;
+type Result<T> = std::result::Result<T, CompileError>;
+
+const ADD: Int = 1;
+const MULT: Int = 2;
+const INPUT: Int = 3;
+const OUTPUT: Int = 4;
+const JUMP_IF_TRUE: Int = 5;
+const JUMP_IF_FALSE: Int = 6;
+const LESS_THAN: Int = 7;
+const EQUALS: Int = 8;
+const RELATIVE_BASE_OFFSET: Int = 9;
+const HALT: Int = 99;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Immediate(Int),
+    /// A slot in the current stack frame.
+    Relative(Int),
+    /// The memory cell at a label.
+    Cell(Label),
+    /// The address of a label as an immediate value.
+    Address(Label),
+}
+
+/// Generates an image for `functions`.
+///
+/// The relative base points at the current stack frame. Slot 0 holds the
+/// return address and the arguments follow, then locals and temporaries.
+/// A caller builds the callee's frame just past its own live slots, moves the
+/// relative base there and jumps. The callee leaves its result in a global
+/// return register and jumps back through slot 0, after which the caller
+/// moves the relative base back. The stack starts right after the code.
+pub(super) fn generate(functions: &[Function]) -> Result<Vec<Int>> {
+    let mut emitter = Emitter::default();
+    let mut signatures = HashMap::new();
+    for function in functions {
+        if function.name == "read" {
+            return Err(CompileError::new(function.line, "`read` is a builtin"));
+        }
+        let signature = (emitter.new_label(), function.parameters.len());
+        if signatures
+            .insert(function.name.as_str(), signature)
+            .is_some()
+        {
+            let message = format!("function `{}` is defined twice", function.name);
+            return Err(CompileError::new(function.line, &message));
+        }
+    }
+    let main = match signatures.get("main") {
+        Some(&(label, 0)) => label,
+        Some(_) => return Err(CompileError::new(1, "`main` can't take parameters")),
+        None => return Err(CompileError::new(1, "no `main` function")),
+    };
+    let return_register = emitter.new_label();
+    let stack = emitter.new_label();
+
+    let exit = emitter.new_label();
+    emitter.emit(RELATIVE_BASE_OFFSET, &[Operand::Address(stack)]);
+    emitter.emit(
+        ADD,
+        &[
+            Operand::Immediate(0),
+            Operand::Address(exit),
+            Operand::Relative(0),
+        ],
+    );
+    emitter.emit(
+        JUMP_IF_TRUE,
+        &[Operand::Immediate(1), Operand::Address(main)],
+    );
+    emitter.place(exit);
+    emitter.emit(HALT, &[]);
+
+    for function in functions {
+        let mut generator = FunctionGenerator {
+            emitter: &mut emitter,
+            signatures: &signatures,
+            return_register,
+            scopes: vec![HashMap::new()],
+            next_slot: 1,
+        };
+        generator.generate(function)?;
+    }
+
+    emitter.place(return_register);
+    emitter.code.push(0);
+    emitter.place(stack);
+    Ok(emitter.finish())
+}
+
+#[derive(Debug, Default)]
+struct Emitter {
+    code: Vec<Int>,
+    labels: Vec<Option<usize>>,
+    fixups: Vec<(usize, Label)>,
+}
+
+impl Emitter {
+    fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: Label) {
+        self.labels[label] = Some(self.code.len());
+    }
+
+    fn emit(&mut self, opcode: Int, operands: &[Operand]) {
+        let mut instruction = opcode;
+        let mut mode_multiplier = 100;
+        for operand in operands {
+            let mode = match operand {
+                Operand::Cell(_) => 0,
+                Operand::Immediate(_) | Operand::Address(_) => 1,
+                Operand::Relative(_) => 2,
+            };
+            instruction += mode * mode_multiplier;
+            mode_multiplier *= 10;
+        }
+        self.code.push(instruction);
+        for &operand in operands {
+            let value = match operand {
+                Operand::Immediate(value) | Operand::Relative(value) => value,
+                Operand::Cell(label) | Operand::Address(label) => {
+                    self.fixups.push((self.code.len(), label));
+                    0
+                }
+            };
+            self.code.push(value);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Int> {
+        for &(index, label) in &self.fixups {
+            self.code[index] = self.labels[label].expect("label was never placed") as Int;
+        }
+        self.code
+    }
+}
+
+struct FunctionGenerator<'a> {
+    emitter: &'a mut Emitter,
+    signatures: &'a HashMap<&'a str, (Label, usize)>,
+    return_register: Label,
+    scopes: Vec<HashMap<String, Int>>,
+    next_slot: Int,
+}
+
+impl FunctionGenerator<'_> {
+    fn generate(&mut self, function: &Function) -> Result<()> {
+        let (label, _) = self.signatures[function.name.as_str()];
+        self.emitter.place(label);
+        for parameter in &function.parameters {
+            let slot = self.allocate();
+            self.scopes[0].insert(parameter.clone(), slot);
+        }
+        self.block(&function.body)?;
+        self.return_value(Operand::Immediate(0));
+        Ok(())
+    }
+
+    fn allocate(&mut self) -> Int {
+        self.next_slot += 1;
+        self.next_slot - 1
+    }
+
+    fn lookup(&self, line: usize, name: &str) -> Result<Int> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(&slot) => Ok(slot),
+            None => {
+                let message = format!("undefined variable `{}`", name);
+                Err(CompileError::new(line, &message))
+            }
+        }
+    }
+
+    fn copy(&mut self, value: Operand, destination: Operand) {
+        if value != destination {
+            self.emitter
+                .emit(ADD, &[value, Operand::Immediate(0), destination]);
+        }
+    }
+
+    fn return_value(&mut self, value: Operand) {
+        self.copy(value, Operand::Cell(self.return_register));
+        self.emitter
+            .emit(JUMP_IF_TRUE, &[Operand::Immediate(1), Operand::Relative(0)]);
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> Result<()> {
+        let next_slot = self.next_slot;
+        self.scopes.push(HashMap::new());
+        for statement in statements {
+            self.statement(statement)?;
+        }
+        self.scopes.pop();
+        self.next_slot = next_slot;
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<()> {
+        let next_slot = self.next_slot;
+        match statement {
+            Statement::Let(_, name, value) => {
+                let value = self.expression(value)?;
+                self.next_slot = next_slot;
+                let slot = self.allocate();
+                self.copy(value, Operand::Relative(slot));
+                let scope = self.scopes.last_mut().unwrap();
+                scope.insert(name.clone(), slot);
+                return Ok(());
+            }
+            Statement::Assign(line, name, value) => {
+                let slot = self.lookup(*line, name)?;
+                let value = self.expression(value)?;
+                self.copy(value, Operand::Relative(slot));
+            }
+            Statement::If(condition, then_block, else_block) => {
+                let condition = self.expression(condition)?;
+                let else_label = self.emitter.new_label();
+                self.emitter
+                    .emit(JUMP_IF_FALSE, &[condition, Operand::Address(else_label)]);
+                self.block(then_block)?;
+                if else_block.is_empty() {
+                    self.emitter.place(else_label);
+                } else {
+                    let end = self.emitter.new_label();
+                    self.emitter.emit(
+                        JUMP_IF_TRUE,
+                        &[Operand::Immediate(1), Operand::Address(end)],
+                    );
+                    self.emitter.place(else_label);
+                    self.block(else_block)?;
+                    self.emitter.place(end);
+                }
+            }
+            Statement::While(condition, body) => {
+                let start = self.emitter.new_label();
+                let end = self.emitter.new_label();
+                self.emitter.place(start);
+                let condition = self.expression(condition)?;
+                self.emitter
+                    .emit(JUMP_IF_FALSE, &[condition, Operand::Address(end)]);
+                self.block(body)?;
+                self.emitter.emit(
+                    JUMP_IF_TRUE,
+                    &[Operand::Immediate(1), Operand::Address(start)],
+                );
+                self.emitter.place(end);
+            }
+            Statement::Return(value) => {
+                let value = self.expression(value)?;
+                self.return_value(value);
+            }
+            Statement::Print(value) => {
+                let value = self.expression(value)?;
+                self.emitter.emit(OUTPUT, &[value]);
+            }
+            Statement::Expression(value) => {
+                self.expression(value)?;
+            }
+        }
+        self.next_slot = next_slot;
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<Operand> {
+        let operand = match expression {
+            Expression::Number(value) => Operand::Immediate(*value),
+            Expression::Variable(line, name) => Operand::Relative(self.lookup(*line, name)?),
+            Expression::Read => {
+                let result = Operand::Relative(self.allocate());
+                self.emitter.emit(INPUT, &[result]);
+                result
+            }
+            Expression::Negate(value) => match self.expression(value)? {
+                Operand::Immediate(value) => Operand::Immediate(-value),
+                value => {
+                    let result = Operand::Relative(self.allocate());
+                    self.emitter
+                        .emit(MULT, &[value, Operand::Immediate(-1), result]);
+                    result
+                }
+            },
+            Expression::Not(value) => {
+                let value = self.expression(value)?;
+                let result = Operand::Relative(self.allocate());
+                self.emitter
+                    .emit(EQUALS, &[value, Operand::Immediate(0), result]);
+                result
+            }
+            Expression::Binary(operator, a, b) => {
+                let a = self.expression(a)?;
+                let b = self.expression(b)?;
+                self.binary(*operator, a, b)
+            }
+            Expression::Call(line, name, arguments) => self.call(*line, name, arguments)?,
+        };
+        Ok(operand)
+    }
+
+    fn binary(&mut self, operator: BinaryOperator, a: Operand, b: Operand) -> Operand {
+        use BinaryOperator::*;
+        let zero = Operand::Immediate(0);
+        let result = Operand::Relative(self.allocate());
+        let emitter = &mut *self.emitter;
+        match operator {
+            Add => emitter.emit(ADD, &[a, b, result]),
+            Subtract => {
+                emitter.emit(MULT, &[b, Operand::Immediate(-1), result]);
+                emitter.emit(ADD, &[a, result, result]);
+            }
+            Multiply => emitter.emit(MULT, &[a, b, result]),
+            Equal => emitter.emit(EQUALS, &[a, b, result]),
+            NotEqual => {
+                emitter.emit(EQUALS, &[a, b, result]);
+                emitter.emit(EQUALS, &[result, zero, result]);
+            }
+            LessThan => emitter.emit(LESS_THAN, &[a, b, result]),
+            GreaterThan => emitter.emit(LESS_THAN, &[b, a, result]),
+            LessThanOrEqual => {
+                emitter.emit(LESS_THAN, &[b, a, result]);
+                emitter.emit(EQUALS, &[result, zero, result]);
+            }
+            GreaterThanOrEqual => {
+                emitter.emit(LESS_THAN, &[a, b, result]);
+                emitter.emit(EQUALS, &[result, zero, result]);
+            }
+            // both operands are always evaluated
+            And | Or => {
+                let b_is_zero = Operand::Relative(self.allocate());
+                let emitter = &mut *self.emitter;
+                emitter.emit(EQUALS, &[a, zero, result]);
+                emitter.emit(EQUALS, &[b, zero, b_is_zero]);
+                if operator == And {
+                    emitter.emit(ADD, &[result, b_is_zero, result]);
+                } else {
+                    emitter.emit(MULT, &[result, b_is_zero, result]);
+                }
+                emitter.emit(EQUALS, &[result, zero, result]);
+            }
+        }
+        result
+    }
+
+    fn call(&mut self, line: usize, name: &str, arguments: &[Expression]) -> Result<Operand> {
+        let (label, parameter_count) = match self.signatures.get(name) {
+            Some(&signature) => signature,
+            None => {
+                let message = format!("undefined function `{}`", name);
+                return Err(CompileError::new(line, &message));
+            }
+        };
+        if arguments.len() != parameter_count {
+            let message = format!(
+                "`{}` takes {} arguments but {} were given",
+                name,
+                parameter_count,
+                arguments.len()
+            );
+            return Err(CompileError::new(line, &message));
+        }
+        let frame = self.next_slot;
+        self.next_slot += 1 + arguments.len() as Int;
+        for (i, argument) in arguments.iter().enumerate() {
+            let value = self.expression(argument)?;
+            self.copy(value, Operand::Relative(frame + 1 + i as Int));
+        }
+        let return_address = self.emitter.new_label();
+        let emitter = &mut *self.emitter;
+        emitter.emit(
+            ADD,
+            &[
+                Operand::Immediate(0),
+                Operand::Address(return_address),
+                Operand::Relative(frame),
+            ],
+        );
+        emitter.emit(RELATIVE_BASE_OFFSET, &[Operand::Immediate(frame)]);
+        emitter.emit(
+            JUMP_IF_TRUE,
+            &[Operand::Immediate(1), Operand::Address(label)],
+        );
+        emitter.place(return_address);
+        emitter.emit(RELATIVE_BASE_OFFSET, &[Operand::Immediate(-frame)]);
+        self.next_slot = frame;
+        let result = Operand::Relative(self.allocate());
+        self.copy(Operand::Cell(self.return_register), result);
+        Ok(result)
+    }
+}
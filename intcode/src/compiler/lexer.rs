@@ -0,0 +1,66 @@
+use super::CompileError;
+use crate::Int;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Token {
+    Number(Int),
+    Identifier(String),
+    Keyword(&'static str),
+    Symbol(&'static str),
+}
+
+const KEYWORDS: [&str; 7] = ["fn", "let", "if", "else", "while", "return", "print"];
+
+// longer symbols first so that `<=` isn't read as `<`
+const SYMBOLS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "+", "-", "*", "=", "(", ")", "{", "}", ",",
+    ";",
+];
+
+/// Splits `source` into tokens paired with their line numbers. `//` starts a
+/// comment that runs to the end of the line.
+pub(super) fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, CompileError> {
+    let mut tokens = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.split("//").next().unwrap();
+        let mut rest = line.trim_start();
+        while !rest.is_empty() {
+            let c = rest.chars().next().unwrap();
+            let length = if c.is_ascii_digit() {
+                let length = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let value = rest[..length]
+                    .parse()
+                    .map_err(|_| CompileError::new(line_number, "integer literal is too large"))?;
+                tokens.push((line_number, Token::Number(value)));
+                length
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let length = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                let word = &rest[..length];
+                let token = match KEYWORDS.iter().find(|&&keyword| keyword == word) {
+                    Some(keyword) => Token::Keyword(keyword),
+                    None => Token::Identifier(word.to_string()),
+                };
+                tokens.push((line_number, token));
+                length
+            } else {
+                match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+                    Some(symbol) => {
+                        tokens.push((line_number, Token::Symbol(symbol)));
+                        symbol.len()
+                    }
+                    None => {
+                        let message = format!("unexpected character `{}`", c);
+                        return Err(CompileError::new(line_number, &message));
+                    }
+                }
+            };
+            rest = rest[length..].trim_start();
+        }
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,222 @@
+//! A compiler from a small language to Intcode images.
+//!
+//! ```text
+//! // prints the nth Fibonacci number for each input until it reads 0
+//! fn main() {
+//!     let n = read();
+//!     while n != 0 {
+//!         print(fib(n));
+//!         n = read();
+//!     }
+//! }
+//!
+//! fn fib(n) {
+//!     if n < 2 {
+//!         return n;
+//!     }
+//!     return fib(n - 1) + fib(n - 2);
+//! }
+//! ```
+//!
+//! Every value is an integer. Functions take any number of parameters and
+//! return 0 unless they `return` a value, and `let` declares a variable scoped
+//! to the enclosing block. Expressions support `+ - *`, the comparisons
+//! `== != < <= > >=`, and `! && ||`, which treat any non-zero value as true
+//! and always evaluate both operands. `read()` takes the next input and
+//! `print(value)` outputs a value.
+
+mod codegen;
+mod lexer;
+mod parser;
+
+use crate::Int;
+use parser::Parser;
+use std::fmt;
+
+/// Compiles `source` to an image runnable with `Program::from_memory`.
+pub fn compile(source: &str) -> Result<Vec<Int>, CompileError> {
+    let tokens = lexer::tokenize(source)?;
+    let functions = Parser::new(tokens).parse_functions()?;
+    codegen::generate(&functions)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl CompileError {
+    fn new(line: usize, message: &str) -> Self {
+        Self {
+            line,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Program;
+
+    fn run(source: &str, inputs: &[Int]) -> Vec<Int> {
+        let image = compile(source).unwrap();
+        let mut program = Program::from_memory(image);
+        let outputs = program.run(inputs);
+        assert!(program.is_finished());
+        outputs
+    }
+
+    fn error(source: &str) -> CompileError {
+        compile(source).unwrap_err()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let source = "
+            fn main() {
+                print(1 + 2 * 3);
+                print((1 + 2) * 3);
+                print(10 - 4 - 3);
+                print(-read() * 2);
+                let x = read();
+                print(x * x - x);
+            }
+        ";
+        assert_eq!(run(source, &[5, 7]), vec![7, 9, 3, -10, 42]);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let source = "
+            fn main() {
+                let a = read();
+                let b = read();
+                print(a < b);
+                print(a <= b);
+                print(a > b);
+                print(a >= b);
+                print(a == b);
+                print(a != b);
+                print(a && b);
+                print(a || b);
+                print(!a);
+            }
+        ";
+        assert_eq!(run(source, &[2, 2]), vec![0, 1, 0, 1, 1, 0, 1, 1, 0]);
+        assert_eq!(run(source, &[0, 3]), vec![1, 1, 0, 0, 0, 1, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let source = "
+            fn main() {
+                let n = read();
+                let i = 0;
+                let sum = 0;
+                while i < n {
+                    i = i + 1;
+                    if i == 3 {
+                        print(-1);
+                    } else if i > 4 {
+                        let i = 100;
+                        sum = sum + i;
+                    } else {
+                        sum = sum + i;
+                    }
+                }
+                print(sum);
+            }
+        ";
+        assert_eq!(run(source, &[6]), vec![-1, 207]);
+    }
+
+    #[test]
+    fn test_functions() {
+        let source = "
+            fn main() {
+                let n = read();
+                while n != 0 {
+                    print(fib(n));
+                    n = read();
+                }
+                print(sum3(1, square(2), sum3(3, 4, 5)));
+                print(nothing());
+            }
+
+            fn fib(n) {
+                if n < 2 {
+                    return n;
+                }
+                return fib(n - 1) + fib(n - 2);
+            }
+
+            fn square(x) {
+                return x * x;
+            }
+
+            fn sum3(a, b, c) {
+                return a + b + c;
+            }
+
+            fn nothing() {}
+        ";
+        assert_eq!(run(source, &[1, 10, 20, 0]), vec![1, 55, 6765, 17, 0]);
+    }
+
+    #[test]
+    fn test_recursion_with_locals() {
+        let source = "
+            fn main() {
+                print(factorial(read()));
+            }
+
+            // keeps a local alive across the recursive call
+            fn factorial(n) {
+                let m = n;
+                if n == 0 {
+                    return 1;
+                }
+                let rest = factorial(n - 1);
+                return m * rest;
+            }
+        ";
+        assert_eq!(run(source, &[15]), vec![1_307_674_368_000]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            error("fn main() {\n  print(x);\n}"),
+            CompileError::new(2, "undefined variable `x`")
+        );
+        assert_eq!(
+            error("fn main() {\n  f(1);\n}\nfn f(a, b) {}"),
+            CompileError::new(2, "`f` takes 2 arguments but 1 were given")
+        );
+        assert_eq!(error("fn f() {}").message, "no `main` function");
+        assert_eq!(error("fn main() {\n  print(1)\n}").line, 3);
+        assert_eq!(
+            error("fn main() { let = 1; }").message,
+            "expected identifier"
+        );
+        assert_eq!(error("fn main() { $ }").message, "unexpected character `$`");
+        assert_eq!(
+            error("fn main() {}\nfn main() {}"),
+            CompileError::new(2, "function `main` is defined twice")
+        );
+        assert_eq!(
+            error("fn main() { print(1 +").message,
+            "expected expression at end of input"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use super::lexer::Token;
+use super::CompileError;
+use crate::Int;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Function {
+    pub line: usize,
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Statement {
+    Let(usize, String, Expression),
+    Assign(usize, String, Expression),
+    If(Expression, Vec<Statement>, Vec<Statement>),
+    While(Expression, Vec<Statement>),
+    Return(Expression),
+    Print(Expression),
+    Expression(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Expression {
+    Number(Int),
+    Variable(usize, String),
+    Call(usize, String, Vec<Expression>),
+    Read,
+    Negate(Box<Expression>),
+    Not(Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    And,
+    Or,
+}
+
+type Result<T> = std::result::Result<T, CompileError>;
+
+pub(super) struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<(usize, Token)>) -> Self {
+        Self { tokens, index: 0 }
+    }
+
+    pub fn parse_functions(&mut self) -> Result<Vec<Function>> {
+        let mut functions = vec![];
+        while self.index < self.tokens.len() {
+            functions.push(self.parse_function()?);
+        }
+        Ok(functions)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |&(line, _)| line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn next_is(&self, symbol: &str) -> bool {
+        match self.peek() {
+            Some(Token::Symbol(s)) | Some(Token::Keyword(s)) => *s == symbol,
+            _ => false,
+        }
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = self.next_is(symbol);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<()> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", symbol)))
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.index += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("expected identifier")),
+        }
+    }
+
+    fn error(&self, message: &str) -> CompileError {
+        let message = match self.peek() {
+            Some(_) => message.to_string(),
+            None => format!("{} at end of input", message),
+        };
+        CompileError::new(self.line(), &message)
+    }
+
+    fn parse_function(&mut self) -> Result<Function> {
+        let line = self.line();
+        self.expect("fn")?;
+        let name = self.expect_identifier()?;
+        self.expect("(")?;
+        let mut parameters = vec![];
+        while !self.eat(")") {
+            if !parameters.is_empty() {
+                self.expect(",")?;
+            }
+            parameters.push(self.expect_identifier()?);
+        }
+        let body = self.parse_block()?;
+        Ok(Function {
+            line,
+            name,
+            parameters,
+            body,
+        })
+    }
+
+    fn parse_block(&mut self) -> Result<Vec<Statement>> {
+        self.expect("{")?;
+        let mut statements = vec![];
+        while !self.eat("}") {
+            statements.push(self.parse_statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement> {
+        let line = self.line();
+        let statement = if self.eat("let") {
+            let name = self.expect_identifier()?;
+            self.expect("=")?;
+            Statement::Let(line, name, self.parse_expression()?)
+        } else if self.eat("if") {
+            return self.parse_if();
+        } else if self.eat("while") {
+            let condition = self.parse_expression()?;
+            return Ok(Statement::While(condition, self.parse_block()?));
+        } else if self.eat("return") {
+            Statement::Return(self.parse_expression()?)
+        } else if self.eat("print") {
+            self.expect("(")?;
+            let value = self.parse_expression()?;
+            self.expect(")")?;
+            Statement::Print(value)
+        } else if let (Some(Token::Identifier(name)), Some((_, Token::Symbol("=")))) =
+            (self.peek(), self.tokens.get(self.index + 1))
+        {
+            let name = name.clone();
+            self.index += 2;
+            Statement::Assign(line, name, self.parse_expression()?)
+        } else {
+            Statement::Expression(self.parse_expression()?)
+        };
+        self.expect(";")?;
+        Ok(statement)
+    }
+
+    fn parse_if(&mut self) -> Result<Statement> {
+        let condition = self.parse_expression()?;
+        let then_block = self.parse_block()?;
+        let else_block = if !self.eat("else") {
+            vec![]
+        } else if self.eat("if") {
+            vec![self.parse_if()?]
+        } else {
+            self.parse_block()?
+        };
+        Ok(Statement::If(condition, then_block, else_block))
+    }
+
+    pub fn parse_expression(&mut self) -> Result<Expression> {
+        self.parse_binary(0)
+    }
+
+    // operators grouped from lowest to highest precedence
+    fn parse_binary(&mut self, level: usize) -> Result<Expression> {
+        use BinaryOperator::*;
+        const LEVELS: [&[(&str, BinaryOperator)]; 5] = [
+            &[("||", Or)],
+            &[("&&", And)],
+            &[
+                ("==", Equal),
+                ("!=", NotEqual),
+                ("<=", LessThanOrEqual),
+                (">=", GreaterThanOrEqual),
+                ("<", LessThan),
+                (">", GreaterThan),
+            ],
+            &[("+", Add), ("-", Subtract)],
+            &[("*", Multiply)],
+        ];
+        if level == LEVELS.len() {
+            return self.parse_unary();
+        }
+        let mut expression = self.parse_binary(level + 1)?;
+        while let Some(&(_, operator)) = LEVELS[level]
+            .iter()
+            .find(|(symbol, _)| self.next_is(symbol))
+        {
+            self.index += 1;
+            let rhs = self.parse_binary(level + 1)?;
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(rhs));
+        }
+        Ok(expression)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression> {
+        if self.eat("-") {
+            Ok(Expression::Negate(Box::new(self.parse_unary()?)))
+        } else if self.eat("!") {
+            Ok(Expression::Not(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression> {
+        let line = self.line();
+        match self.peek().cloned() {
+            Some(Token::Number(value)) => {
+                self.index += 1;
+                Ok(Expression::Number(value))
+            }
+            Some(Token::Identifier(name)) => {
+                self.index += 1;
+                if !self.eat("(") {
+                    return Ok(Expression::Variable(line, name));
+                }
+                let mut arguments = vec![];
+                while !self.eat(")") {
+                    if !arguments.is_empty() {
+                        self.expect(",")?;
+                    }
+                    arguments.push(self.parse_expression()?);
+                }
+                if name == "read" && arguments.is_empty() {
+                    Ok(Expression::Read)
+                } else {
+                    Ok(Expression::Call(line, name, arguments))
+                }
+            }
+            Some(Token::Symbol("(")) => {
+                self.index += 1;
+                let expression = self.parse_expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            _ => Err(self.error("expected expression")),
+        }
+    }
+}
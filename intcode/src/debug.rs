@@ -0,0 +1,208 @@
+use crate::{Expression, Int, ParseExpressionError, Program, ProgramState};
+
+type InputHook = Box<dyn FnMut(&Program, Option<Int>) -> Option<Int>>;
+type OutputHook = Box<dyn FnMut(&Program, Int) -> Int>;
+
+/// A condition checked before each instruction is executed.
+pub enum Breakpoint {
+    Address(usize),
+    Condition(Expression),
+    Custom(Box<dyn Fn(&Program) -> bool>),
+}
+
+impl Breakpoint {
+    fn is_hit(&self, program: &Program) -> bool {
+        match self {
+            Self::Address(address) => program.instruction_pointer() == *address,
+            Self::Condition(expression) => expression.is_true(program),
+            Self::Custom(condition) => condition(program),
+        }
+    }
+}
+
+/// Runs a `Program` with breakpoints and I/O hooks.
+///
+/// When a breakpoint is hit the program is left in the `Paused` state before
+/// the instruction executes. Calling `run` again resumes from that instruction.
+pub struct Debugger {
+    program: Program,
+    breakpoints: Vec<Breakpoint>,
+    input_hooks: Vec<InputHook>,
+    output_hooks: Vec<OutputHook>,
+    last_breakpoint: Option<usize>,
+}
+
+impl Debugger {
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            breakpoints: vec![],
+            input_hooks: vec![],
+            output_hooks: vec![],
+            last_breakpoint: None,
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn program_mut(&mut self) -> &mut Program {
+        &mut self.program
+    }
+
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.program.is_finished()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.program.state == ProgramState::Paused
+    }
+
+    /// Index of the breakpoint that paused the program, if any.
+    pub fn last_breakpoint(&self) -> Option<usize> {
+        self.last_breakpoint
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    /// Parses `condition` as an `Expression` and breaks whenever it is non-zero.
+    pub fn break_when(&mut self, condition: &str) -> Result<usize, ParseExpressionError> {
+        let expression = condition.parse()?;
+        Ok(self.add_breakpoint(Breakpoint::Condition(expression)))
+    }
+
+    /// Registers a hook called before each `Input` instruction with the pending
+    /// input, if any. Returning `Some` replaces the pending input, or supplies
+    /// one when the program would otherwise wait for input.
+    pub fn on_input<F>(&mut self, hook: F)
+    where
+        F: FnMut(&Program, Option<Int>) -> Option<Int> + 'static,
+    {
+        self.input_hooks.push(Box::new(hook));
+    }
+
+    /// Registers a hook called with each output value, returning the value
+    /// that should be output instead.
+    pub fn on_output<F>(&mut self, hook: F)
+    where
+        F: FnMut(&Program, Int) -> Int + 'static,
+    {
+        self.output_hooks.push(Box::new(hook));
+    }
+
+    pub fn run(&mut self, inputs: &[Int]) -> Vec<Int> {
+        let program = &mut self.program;
+        let resuming = program.state == ProgramState::Paused;
+        program.outputs.clear();
+        program.inputs.extend_from_slice(inputs);
+        program.state = ProgramState::Running;
+        self.last_breakpoint = None;
+        let mut first_step = true;
+        while self.program.state == ProgramState::Running {
+            if !(resuming && first_step) {
+                let program = &self.program;
+                self.last_breakpoint = self.breakpoints.iter().position(|b| b.is_hit(program));
+                if self.last_breakpoint.is_some() {
+                    self.program.state = ProgramState::Paused;
+                    break;
+                }
+            }
+            first_step = false;
+            self.step();
+        }
+        self.program.outputs.clone()
+    }
+
+    fn step(&mut self) {
+        let program = &mut self.program;
+        if program.opcode() == 3 {
+            let mut pending = program.inputs.first().copied();
+            let mut replaced = false;
+            for hook in self.input_hooks.iter_mut() {
+                if let Some(value) = hook(program, pending) {
+                    pending = Some(value);
+                    replaced = true;
+                }
+            }
+            if replaced {
+                let value = pending.unwrap();
+                match program.inputs.first_mut() {
+                    Some(input) => *input = value,
+                    None => program.inputs.push(value),
+                }
+            }
+        }
+        let output_count = program.outputs.len();
+        program.step();
+        if program.outputs.len() > output_count {
+            let mut value = program.outputs.pop().unwrap();
+            for hook in self.output_hooks.iter_mut() {
+                value = hook(program, value);
+            }
+            program.outputs.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    // Reads a value into address 20 and outputs it doubled, forever.
+    const DOUBLER: &str = "3,20,1002,20,2,21,4,21,1105,1,0";
+
+    #[test]
+    fn test_address_breakpoint() {
+        let mut debugger = Debugger::new(Program::from_input(DOUBLER));
+        debugger.add_breakpoint(Breakpoint::Address(6));
+        assert_eq!(debugger.run(&[4, 5]), vec![]);
+        assert!(debugger.is_paused());
+        assert_eq!(debugger.last_breakpoint(), Some(0));
+        assert_eq!(debugger.run(&[]), vec![8]);
+        assert!(debugger.is_paused());
+        assert_eq!(debugger.run(&[]), vec![10]);
+        assert_eq!(debugger.program().state(), ProgramState::WaitingForInput);
+    }
+
+    #[test]
+    fn test_conditional_breakpoint() {
+        let mut debugger = Debugger::new(Program::from_input(DOUBLER));
+        debugger.break_when("mem[21] > 5 && ip == 6").unwrap();
+        assert_eq!(debugger.run(&[1, 2, 3, 4]), vec![2, 4]);
+        assert_eq!(debugger.program().read_from_memory(21), 6);
+        debugger.add_breakpoint(Breakpoint::Custom(Box::new(|p| {
+            p.read_from_memory(20) == 4
+        })));
+        assert_eq!(debugger.run(&[]), vec![6]);
+        assert_eq!(debugger.last_breakpoint(), Some(1));
+    }
+
+    #[test]
+    fn test_io_hooks() {
+        let mut debugger = Debugger::new(Program::from_input(DOUBLER));
+        let last_output = Rc::new(Cell::new(0));
+        let output = Rc::clone(&last_output);
+        debugger.on_output(move |_, value| {
+            output.set(value);
+            value + 1
+        });
+        let output = Rc::clone(&last_output);
+        debugger.on_input(move |_, pending| match pending {
+            Some(_) => None,
+            None if output.get() < 20 => Some(output.get()),
+            None => None,
+        });
+        assert_eq!(debugger.run(&[1]), vec![3, 5, 9, 17, 33]);
+        assert_eq!(debugger.program().state(), ProgramState::WaitingForInput);
+    }
+}
@@ -0,0 +1,588 @@
+use crate::disasm::{DecodedInstruction, Disassembly, Operand, Operation};
+use crate::Int;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Decompiles a program image into pseudo-code, one function at a time.
+///
+/// Memory at a fixed address is shown as `m[address]` and the stack frame at
+/// the relative base as `fp[offset]`. Operands the program overwrites are
+/// pointers: writes to them become assignments to `p<address>` and the
+/// operand itself is shown as `m[p<address>]`. A jump to a constant address
+/// saved just before it is a call, and a jump through the frame is a return.
+/// When a function grows the frame on entry and shrinks it before returning,
+/// offsets are shown from the frame on entry, so a caller's `fp[1]` is the
+/// same cell as its callee's.
+/// Backward jumps become loops and forward conditional jumps become `if` and
+/// `if ... else` blocks, with `goto` for anything else.
+pub fn decompile(memory: &[Int]) -> String {
+    let decompiler = Decompiler::new(memory);
+    let functions: Vec<_> = decompiler
+        .entries
+        .iter()
+        .map(|&entry| FunctionWriter::new(&decompiler, entry).write())
+        .collect();
+    functions.join("\n")
+}
+
+struct Decompiler {
+    disassembly: Disassembly,
+    /// Calls, mapped to the instruction that saves the return address.
+    calls: HashMap<usize, usize>,
+    /// Operand cells that the program writes to.
+    pointers: HashSet<usize>,
+    entries: BTreeSet<usize>,
+}
+
+impl Decompiler {
+    fn new(memory: &[Int]) -> Self {
+        let disassembly = Disassembly::new(memory);
+        let instructions: Vec<_> = disassembly.instructions().collect();
+        let mut calls = HashMap::new();
+        for (i, jump) in instructions.iter().enumerate() {
+            if !jump.is_unconditional_jump() || jump.jump_target().is_none() {
+                continue;
+            }
+            let return_address = jump.next_address() as Int;
+            let store = instructions[..i]
+                .iter()
+                .rev()
+                .take_while(|instruction| !instruction.is_jump())
+                .find(|instruction| instruction.stored_constant() == Some(return_address));
+            if let Some(store) = store {
+                calls.insert(jump.address, store.address);
+            }
+        }
+        let pointers = instructions
+            .iter()
+            .filter_map(|instruction| instruction.destination())
+            .filter(|&address| {
+                let owner = disassembly.operand_owner(address);
+                owner.is_some_and(|owner| owner.address != address)
+            })
+            .collect();
+        let mut entries: BTreeSet<_> = calls
+            .keys()
+            .filter_map(|&address| disassembly.instruction_at(address)?.jump_target())
+            .collect();
+        entries.insert(0);
+        Self {
+            disassembly,
+            calls,
+            pointers,
+            entries,
+        }
+    }
+
+    fn function_name(&self, entry: usize) -> String {
+        if entry == 0 {
+            String::from("main")
+        } else {
+            format!("f_{}", entry)
+        }
+    }
+
+    /// The instructions reachable from `entry` without entering another
+    /// function, in address order.
+    fn function_body(&self, entry: usize) -> Vec<&DecodedInstruction> {
+        let mut body = BTreeSet::new();
+        let mut pending = vec![entry];
+        while let Some(address) = pending.pop() {
+            if body.contains(&address) || (address != entry && self.entries.contains(&address)) {
+                continue;
+            }
+            let instruction = match self.disassembly.instruction_at(address) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+            body.insert(address);
+            let next = instruction.next_address();
+            if instruction.operation == Operation::Halt {
+                continue;
+            } else if self.calls.contains_key(&address) || instruction.is_never_taken() {
+                pending.push(next);
+            } else if instruction.is_unconditional_jump() {
+                pending.extend(instruction.jump_target());
+            } else {
+                pending.push(next);
+                pending.extend(instruction.jump_target());
+            }
+        }
+        body.iter()
+            .filter_map(|&address| self.disassembly.instruction_at(address))
+            .collect()
+    }
+}
+
+struct FunctionWriter<'a> {
+    decompiler: &'a Decompiler,
+    entry: usize,
+    code: Vec<&'a DecodedInstruction>,
+    frame: Option<Int>,
+    /// Instructions explained by the function's structure, like saving a
+    /// return address or setting up the frame.
+    hidden: HashSet<usize>,
+    /// The head and exit address of each enclosing loop.
+    loops: Vec<(usize, usize)>,
+    labels: BTreeSet<usize>,
+    lines: Vec<(usize, usize, String)>,
+}
+
+impl<'a> FunctionWriter<'a> {
+    fn new(decompiler: &'a Decompiler, entry: usize) -> Self {
+        let code = decompiler.function_body(entry);
+        let mut hidden: HashSet<_> = code
+            .iter()
+            .filter_map(|instruction| decompiler.calls.get(&instruction.address))
+            .copied()
+            .collect();
+        let frame = Self::find_frame(&code);
+        if frame.is_some() {
+            hidden.insert(entry);
+            for (i, instruction) in code.iter().enumerate().skip(1) {
+                if Self::is_return(instruction) {
+                    hidden.insert(code[i - 1].address);
+                }
+            }
+        }
+        Self {
+            decompiler,
+            entry,
+            code,
+            frame,
+            hidden,
+            loops: vec![],
+            labels: BTreeSet::new(),
+            lines: vec![],
+        }
+    }
+
+    fn is_return(instruction: &DecodedInstruction) -> bool {
+        instruction.is_unconditional_jump()
+            && matches!(instruction.operands[1], Operand::Relative(_))
+    }
+
+    /// The size of the stack frame if the function grows it on entry and
+    /// shrinks it again right before every return.
+    fn find_frame(code: &[&DecodedInstruction]) -> Option<Int> {
+        let size = match code.first()?.operands.as_slice() {
+            &[Operand::Immediate(size)] if code[0].operation == Operation::RelativeBaseOffset => {
+                size
+            }
+            _ => return None,
+        };
+        if size <= 0 {
+            return None;
+        }
+        let mut returns = 0;
+        for (i, instruction) in code.iter().enumerate().skip(1) {
+            if Self::is_return(instruction) {
+                let previous = code[i - 1];
+                let shrinks = previous.operation == Operation::RelativeBaseOffset
+                    && previous.operands[0] == Operand::Immediate(-size)
+                    && previous.next_address() == instruction.address;
+                if !shrinks {
+                    return None;
+                }
+                returns += 1;
+            }
+        }
+        if returns > 0 {
+            Some(size)
+        } else {
+            None
+        }
+    }
+
+    fn write(mut self) -> String {
+        let end = self.code.len();
+        self.region(0, end, 1, None);
+        let mut output = format!("fn {}() {{", self.decompiler.function_name(self.entry));
+        if let Some(size) = self.frame {
+            output += &format!(" // frame of {}", size);
+        }
+        output.push('\n');
+        let mut labels = self.labels.iter().peekable();
+        for (address, depth, line) in &self.lines {
+            while let Some(&&label) = labels.peek() {
+                if label > *address {
+                    break;
+                }
+                output += &format!("{}L{}:\n", "    ".repeat(depth - 1), label);
+                labels.next();
+            }
+            output += &format!("{}{}\n", "    ".repeat(*depth), line);
+        }
+        for label in labels {
+            output += &format!("L{}:\n", label);
+        }
+        output + "}\n"
+    }
+
+    fn line(&mut self, address: usize, depth: usize, line: String) {
+        self.lines.push((address, depth, line));
+    }
+
+    fn index_of(&self, address: usize) -> Option<usize> {
+        self.code
+            .binary_search_by_key(&address, |instruction| instruction.address)
+            .ok()
+    }
+
+    fn is_call(&self, instruction: &DecodedInstruction) -> bool {
+        self.decompiler.calls.contains_key(&instruction.address)
+    }
+
+    fn jumps_to(&self, instruction: &DecodedInstruction, target: usize) -> bool {
+        !instruction.is_never_taken()
+            && !self.is_call(instruction)
+            && instruction.jump_target() == Some(target)
+    }
+
+    /// Writes the instructions in `code[start..end]`. `loop_head` is the
+    /// index of a loop head that has already been written.
+    fn region(&mut self, start: usize, end: usize, depth: usize, loop_head: Option<usize>) {
+        let mut i = start;
+        while i < end {
+            let head = self.code[i].address;
+            let back_edge = (i..end).rev().find(|&j| self.jumps_to(self.code[j], head));
+            match back_edge {
+                Some(j) if loop_head != Some(i) => {
+                    self.write_loop(i, j, depth);
+                    i = j + 1;
+                }
+                _ => i = self.statement(i, end, depth),
+            }
+        }
+    }
+
+    fn write_loop(&mut self, start: usize, back_edge: usize, depth: usize) {
+        let head = self.code[start].address;
+        let jump = self.code[back_edge];
+        self.loops.push((head, jump.next_address()));
+        let condition = self.taken_condition(jump);
+        let has_continue = (start..back_edge).any(|j| self.jumps_to(self.code[j], head));
+        match condition {
+            Some(condition) if !has_continue => {
+                self.line(head, depth, String::from("do {"));
+                self.region(start, back_edge, depth + 1, Some(start));
+                self.line(jump.address, depth, format!("}} while {}", condition));
+            }
+            condition => {
+                self.line(head, depth, String::from("loop {"));
+                self.region(start, back_edge, depth + 1, Some(start));
+                if let Some(condition) = condition {
+                    let exit = format!("if {} {{ break }}", negate(&condition));
+                    self.line(jump.address, depth + 1, exit);
+                }
+                self.line(jump.address, depth, String::from("}"));
+            }
+        }
+        self.loops.pop();
+    }
+
+    /// Writes the statement at `code[i]` and returns the index after it.
+    fn statement(&mut self, i: usize, end: usize, depth: usize) -> usize {
+        let instruction = self.code[i];
+        let address = instruction.address;
+        if self.hidden.contains(&address) || instruction.is_never_taken() {
+            return i + 1;
+        }
+        if instruction.is_jump() {
+            return self.jump(i, end, depth);
+        }
+        let operand = |index| self.operand(instruction, index);
+        let line = match instruction.operation {
+            Operation::Add => arithmetic("+", "0", operand(0), operand(1), operand(2)),
+            Operation::Multiply => arithmetic("*", "1", operand(0), operand(1), operand(2)),
+            Operation::LessThan => format!("{} = {} < {}", operand(2), operand(0), operand(1)),
+            Operation::Equals => format!("{} = {} == {}", operand(2), operand(0), operand(1)),
+            Operation::Input => format!("{} = input()", operand(0)),
+            Operation::Output => format!("output({})", operand(0)),
+            Operation::RelativeBaseOffset => match instruction.operands[0] {
+                Operand::Immediate(value) if value < 0 => format!("fp -= {}", value.unsigned_abs()),
+                _ => format!("fp += {}", operand(0)),
+            },
+            Operation::Halt => String::from("halt"),
+            Operation::JumpIfTrue | Operation::JumpIfFalse => unreachable!(),
+        };
+        self.line(address, depth, line);
+        i + 1
+    }
+
+    fn jump(&mut self, i: usize, end: usize, depth: usize) -> usize {
+        let instruction = self.code[i];
+        let address = instruction.address;
+        let condition = self.taken_condition(instruction);
+        let target = match instruction.jump_target() {
+            Some(target) => target,
+            None => {
+                let statement = if Self::is_return(instruction) {
+                    String::from("return")
+                } else {
+                    format!("goto *{}", self.operand(instruction, 1))
+                };
+                self.conditional(address, depth, condition, statement);
+                return i + 1;
+            }
+        };
+        if self.is_call(instruction) {
+            let call = format!("{}()", self.decompiler.function_name(target));
+            self.line(address, depth, call);
+            return i + 1;
+        }
+        if let Some(&(head, exit)) = self.loops.last() {
+            let statement = if target == exit {
+                Some("break")
+            } else if target == head {
+                Some("continue")
+            } else {
+                None
+            };
+            if let Some(statement) = statement {
+                self.conditional(address, depth, condition, statement.to_string());
+                return i + 1;
+            }
+        }
+        if target != self.entry && self.decompiler.entries.contains(&target) {
+            let call = format!("return {}()", self.decompiler.function_name(target));
+            self.conditional(address, depth, condition, call);
+            return i + 1;
+        }
+        let forward = self.index_of(target).filter(|&k| i < k && k <= end);
+        match (condition, forward) {
+            (Some(condition), Some(k)) => self.write_if(i, k, end, depth, &condition),
+            (condition, _) => {
+                self.labels.insert(target);
+                let statement = format!("goto L{}", target);
+                self.conditional(address, depth, condition, statement);
+                i + 1
+            }
+        }
+    }
+
+    /// Writes an `if` for a conditional jump at `code[i]` over the
+    /// instructions up to its target `code[target]`, with an `else` if they
+    /// end by jumping forward over more instructions.
+    fn write_if(
+        &mut self,
+        i: usize,
+        target: usize,
+        end: usize,
+        depth: usize,
+        condition: &str,
+    ) -> usize {
+        let address = self.code[i].address;
+        self.line(address, depth, format!("if {} {{", negate(condition)));
+        let last = self.code[target - 1];
+        let else_end = last
+            .jump_target()
+            .filter(|_| target - 1 > i && last.is_unconditional_jump() && !self.is_call(last))
+            .filter(|&t| !self.loops.last().is_some_and(|&(h, e)| t == h || t == e))
+            .and_then(|t| self.index_of(t))
+            .filter(|&k| target < k && k <= end);
+        match else_end {
+            Some(else_end) => {
+                self.region(i + 1, target - 1, depth + 1, None);
+                self.line(self.code[target].address, depth, String::from("} else {"));
+                self.region(target, else_end, depth + 1, None);
+                self.line(self.code[else_end - 1].address, depth, String::from("}"));
+                else_end
+            }
+            None => {
+                self.region(i + 1, target, depth + 1, None);
+                self.line(last.address, depth, String::from("}"));
+                target
+            }
+        }
+    }
+
+    fn conditional(
+        &mut self,
+        address: usize,
+        depth: usize,
+        condition: Option<String>,
+        statement: String,
+    ) {
+        let line = match condition {
+            Some(condition) => format!("if {} {{ {} }}", condition, statement),
+            None => statement,
+        };
+        self.line(address, depth, line);
+    }
+
+    /// The condition under which a jump is taken, or `None` if it always is.
+    fn taken_condition(&self, instruction: &DecodedInstruction) -> Option<String> {
+        if instruction.is_unconditional_jump() {
+            return None;
+        }
+        let value = self.operand(instruction, 0);
+        match instruction.operation {
+            Operation::JumpIfTrue => Some(value),
+            _ => Some(negate(&value)),
+        }
+    }
+
+    fn operand(&self, instruction: &DecodedInstruction, index: usize) -> String {
+        let cell = instruction.address + 1 + index;
+        let pointers = &self.decompiler.pointers;
+        match instruction.operands[index] {
+            Operand::Immediate(_) if pointers.contains(&cell) => format!("p{}", cell),
+            Operand::Position(_) if pointers.contains(&cell) => format!("m[p{}]", cell),
+            Operand::Relative(_) if pointers.contains(&cell) => format!("fp[p{}]", cell),
+            Operand::Immediate(value) => value.to_string(),
+            Operand::Position(address) if pointers.contains(&(address as usize)) => {
+                format!("p{}", address)
+            }
+            Operand::Position(address) => format!("m[{}]", address),
+            Operand::Relative(offset) => match offset.checked_add(self.frame.unwrap_or(0)) {
+                Some(offset) => format!("fp[{}]", offset),
+                // too far from the frame to show as a single offset
+                None => format!("fp[{} + {}]", offset, self.frame.unwrap_or(0)),
+            },
+        }
+    }
+}
+
+/// An assignment of `a` and `b` combined with `operator`, simplified when
+/// either is the operator's `identity` or the destination.
+fn arithmetic(operator: &str, identity: &str, a: String, b: String, destination: String) -> String {
+    let (a, b) = if a == identity || b == destination {
+        (b, a)
+    } else {
+        (a, b)
+    };
+    if b == identity {
+        return format!("{} = {}", destination, a);
+    }
+    if operator == "*" && b == "-1" {
+        return format!("{} = -{}", destination, a);
+    }
+    match (a == destination, operator, b.strip_prefix('-')) {
+        (true, "+", Some(b)) => format!("{} -= {}", destination, b),
+        (true, _, _) => format!("{} {}= {}", destination, operator, b),
+        (false, "+", Some(b)) => format!("{} = {} - {}", destination, a, b),
+        (false, _, _) => format!("{} = {} {} {}", destination, a, operator, b),
+    }
+}
+
+fn negate(condition: &str) -> String {
+    match condition.strip_prefix('!') {
+        Some(condition) => condition.to_string(),
+        None => format!("!{}", condition),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_if_else() {
+        let memory = [3, 100, 1006, 100, 10, 104, 1, 1105, 1, 12, 104, 2, 99];
+        assert_eq!(
+            decompile(&memory),
+            "fn main() {
+    m[100] = input()
+    if m[100] {
+        output(1)
+    } else {
+        output(2)
+    }
+    halt
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_extreme_offsets() {
+        let memory = [109, Int::MIN, 204, Int::MIN, 99];
+        assert_eq!(
+            decompile(&memory),
+            "\
+fn main() {
+    fp -= 9223372036854775808
+    output(fp[-9223372036854775808])
+    halt
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_loops() {
+        let memory = [3, 100, 4, 100, 1001, 100, -1, 100, 1005, 100, 2, 99];
+        assert_eq!(
+            decompile(&memory),
+            "fn main() {
+    m[100] = input()
+    do {
+        output(m[100])
+        m[100] -= 1
+    } while m[100]
+    halt
+}
+"
+        );
+
+        // reads values until one is negative, skipping zeros
+        let memory = [
+            3, 100, 1007, 100, 0, 101, 1005, 101, 17, 1006, 100, 0, 4, 100, 1105, 1, 0, 99,
+        ];
+        assert_eq!(
+            decompile(&memory),
+            "fn main() {
+    loop {
+        m[100] = input()
+        m[101] = m[100] < 0
+        if m[101] { break }
+        if !m[100] { continue }
+        output(m[100])
+    }
+    halt
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_functions_and_pointers() {
+        // calls a function that outputs the cell an input points at
+        let memory = [
+            109, 200, 21101, 9, 0, 0, 1105, 1, 10, 99, 109, 2, 3, 15, 4, 0, 109, -2, 2105, 1, 0,
+        ];
+        assert_eq!(
+            decompile(&memory),
+            "fn main() {
+    fp += 200
+    f_10()
+    halt
+}
+
+fn f_10() { // frame of 2
+    p15 = input()
+    output(m[p15])
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_compiled_program() {
+        let source = "
+            fn main() {
+                let n = read();
+                while n != 0 {
+                    print(n * n);
+                    n = read();
+                }
+            }
+        ";
+        let output = decompile(&crate::compile(source).unwrap());
+        assert!(output.contains("input()"));
+        assert!(output.contains("loop {"));
+        assert!(output.contains("return"));
+        assert!(!output.contains("goto"));
+    }
+}
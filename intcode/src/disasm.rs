@@ -0,0 +1,410 @@
+use crate::Int;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Add,
+    Multiply,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    RelativeBaseOffset,
+    Halt,
+}
+
+impl Operation {
+    pub fn from_opcode(opcode: Int) -> Option<Self> {
+        use Operation::*;
+        let operation = match opcode {
+            1 => Add,
+            2 => Multiply,
+            3 => Input,
+            4 => Output,
+            5 => JumpIfTrue,
+            6 => JumpIfFalse,
+            7 => LessThan,
+            8 => Equals,
+            9 => RelativeBaseOffset,
+            99 => Halt,
+            _ => return None,
+        };
+        Some(operation)
+    }
+
+    pub fn parameter_count(self) -> usize {
+        use Operation::*;
+        match self {
+            Add | Multiply | LessThan | Equals => 3,
+            JumpIfTrue | JumpIfFalse => 2,
+            Input | Output | RelativeBaseOffset => 1,
+            Halt => 0,
+        }
+    }
+
+    /// Whether the last parameter is an address the instruction writes to.
+    pub fn writes(self) -> bool {
+        use Operation::*;
+        matches!(self, Add | Multiply | Input | LessThan | Equals)
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        use Operation::*;
+        match self {
+            Add => "add",
+            Multiply => "mul",
+            Input => "in",
+            Output => "out",
+            JumpIfTrue => "jnz",
+            JumpIfFalse => "jz",
+            LessThan => "lt",
+            Equals => "eq",
+            RelativeBaseOffset => "arb",
+            Halt => "hlt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Position(Int),
+    Immediate(Int),
+    Relative(Int),
+}
+
+impl Operand {
+    /// The address the operand refers to, or `None` for immediate values and
+    /// addresses that are negative or out of range.
+    pub fn address(self, relative_base: Int) -> Option<usize> {
+        let address = match self {
+            Self::Position(address) => address,
+            Self::Relative(offset) => relative_base.checked_add(offset)?,
+            Self::Immediate(_) => return None,
+        };
+        if address >= 0 {
+            Some(address as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// A decoded instruction at a fixed address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub address: usize,
+    pub operation: Operation,
+    pub operands: Vec<Operand>,
+}
+
+impl DecodedInstruction {
+    /// Decodes the instruction at `address`, or returns `None` if the value
+    /// there isn't a valid instruction.
+    pub fn decode(memory: &[Int], address: usize) -> Option<Self> {
+        let opcode = *memory.get(address)?;
+        if opcode < 0 {
+            return None;
+        }
+        let operation = Operation::from_opcode(opcode % 100)?;
+        let count = operation.parameter_count();
+        let mut modes = opcode / 100;
+        let mut operands = Vec::with_capacity(count);
+        for i in 0..count {
+            let value = *memory.get(address + 1 + i)?;
+            let operand = match modes % 10 {
+                0 => Operand::Position(value),
+                1 => Operand::Immediate(value),
+                2 => Operand::Relative(value),
+                _ => return None,
+            };
+            operands.push(operand);
+            modes /= 10;
+        }
+        let writes_immediate =
+            operation.writes() && matches!(operands.last(), Some(Operand::Immediate(_)));
+        if modes != 0 || writes_immediate {
+            return None;
+        }
+        Some(Self {
+            address,
+            operation,
+            operands,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.operands.len() + 1
+    }
+
+    pub fn next_address(&self) -> usize {
+        self.address + self.size()
+    }
+
+    /// The address this instruction writes to, if it writes to a fixed one.
+    pub fn destination(&self) -> Option<usize> {
+        match self.operands.last() {
+            Some(&Operand::Position(address)) if self.operation.writes() && address >= 0 => {
+                Some(address as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// The addresses the instruction reads when run with `relative_base`.
+    pub fn read_addresses(&self, relative_base: Int) -> Vec<usize> {
+        let count = if self.operation.writes() {
+            self.operands.len() - 1
+        } else {
+            self.operands.len()
+        };
+        self.operands[..count]
+            .iter()
+            .filter_map(|&operand| operand.address(relative_base))
+            .collect()
+    }
+
+    /// The address the instruction writes when run with `relative_base`.
+    pub fn write_address(&self, relative_base: Int) -> Option<usize> {
+        if self.operation.writes() {
+            self.operands.last()?.address(relative_base)
+        } else {
+            None
+        }
+    }
+
+    /// Whether this is a jump whose condition makes it always taken.
+    pub fn is_unconditional_jump(&self) -> bool {
+        match (self.operation, self.operands.first()) {
+            (Operation::JumpIfTrue, Some(&Operand::Immediate(value))) => value != 0,
+            (Operation::JumpIfFalse, Some(&Operand::Immediate(value))) => value == 0,
+            _ => false,
+        }
+    }
+
+    /// Whether this is a jump whose condition means it's never taken.
+    pub fn is_never_taken(&self) -> bool {
+        match (self.operation, self.operands.first()) {
+            (Operation::JumpIfTrue, Some(&Operand::Immediate(value))) => value == 0,
+            (Operation::JumpIfFalse, Some(&Operand::Immediate(value))) => value != 0,
+            _ => false,
+        }
+    }
+
+    pub fn is_jump(&self) -> bool {
+        matches!(
+            self.operation,
+            Operation::JumpIfTrue | Operation::JumpIfFalse
+        )
+    }
+
+    /// The target of a jump with an immediate target.
+    pub fn jump_target(&self) -> Option<usize> {
+        match self.operands.get(1) {
+            Some(&Operand::Immediate(target)) if self.is_jump() && target >= 0 => {
+                Some(target as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// The constant this instruction stores, if it's an `add` or `mul` of two
+    /// immediate values that doesn't overflow.
+    pub fn stored_constant(&self) -> Option<Int> {
+        use Operand::Immediate;
+        match (self.operation, self.operands.as_slice()) {
+            (Operation::Add, &[Immediate(a), Immediate(b), _]) => a.checked_add(b),
+            (Operation::Multiply, &[Immediate(a), Immediate(b), _]) => a.checked_mul(b),
+            _ => None,
+        }
+    }
+}
+
+/// The instructions of a program found by following its control flow from
+/// address 0, with everything else treated as data.
+///
+/// Jumps to immediate addresses are followed. Jumps through memory can't be,
+/// so code after an unconditional jump is also followed when its address is
+/// stored as a constant somewhere else, which is how programs save return
+/// addresses before calling a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    memory: Vec<Int>,
+    instructions: BTreeMap<usize, DecodedInstruction>,
+}
+
+impl Disassembly {
+    pub fn new(memory: &[Int]) -> Self {
+        let mut disassembly = Self {
+            memory: memory.to_vec(),
+            instructions: BTreeMap::new(),
+        };
+        let mut covered = vec![false; memory.len()];
+        let mut return_sites = BTreeSet::new();
+        let mut pending = vec![0];
+        while !pending.is_empty() {
+            while let Some(address) = pending.pop() {
+                disassembly.trace(address, &mut covered, &mut pending);
+            }
+            let constants: BTreeSet<_> = disassembly
+                .instructions()
+                .filter_map(DecodedInstruction::stored_constant)
+                .collect();
+            pending = disassembly
+                .instructions()
+                .filter(|instruction| instruction.is_unconditional_jump())
+                .map(DecodedInstruction::next_address)
+                .filter(|&address| address < covered.len() && !covered[address])
+                .filter(|&address| constants.contains(&(address as Int)))
+                .filter(|&address| return_sites.insert(address))
+                .collect();
+        }
+        disassembly
+    }
+
+    fn trace(&mut self, mut address: usize, covered: &mut [bool], pending: &mut Vec<usize>) {
+        while address < covered.len() && !covered[address] {
+            let instruction = match DecodedInstruction::decode(&self.memory, address) {
+                Some(instruction) => instruction,
+                None => return,
+            };
+            let cells = address..instruction.next_address();
+            if cells.clone().any(|a| covered[a]) {
+                return;
+            }
+            cells.for_each(|a| covered[a] = true);
+            if !instruction.is_never_taken() {
+                pending.extend(instruction.jump_target());
+            }
+            let falls_through =
+                instruction.operation != Operation::Halt && !instruction.is_unconditional_jump();
+            address = instruction.next_address();
+            self.instructions.insert(instruction.address, instruction);
+            if !falls_through {
+                return;
+            }
+        }
+    }
+
+    pub fn memory(&self) -> &[Int] {
+        &self.memory
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &DecodedInstruction> {
+        self.instructions.values()
+    }
+
+    pub fn instruction_at(&self, address: usize) -> Option<&DecodedInstruction> {
+        self.instructions.get(&address)
+    }
+
+    /// The instruction whose operands include the cell at `address`.
+    pub fn operand_owner(&self, address: usize) -> Option<&DecodedInstruction> {
+        let (_, instruction) = self.instructions.range(..address).next_back()?;
+        if address < instruction.next_address() {
+            Some(instruction)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Position(address) => write!(f, "[{}]", address),
+            Self::Immediate(value) => write!(f, "{}", value),
+            Self::Relative(offset) if offset < 0 => write!(f, "[rb-{}]", offset.unsigned_abs()),
+            Self::Relative(offset) => write!(f, "[rb+{}]", offset),
+        }
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operation.mnemonic())?;
+        for (i, operand) in self.operands.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{}{}", separator, operand)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line per instruction or data cell, prefixed with its address.
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut address = 0;
+        while address < self.memory.len() {
+            match self.instructions.get(&address) {
+                Some(instruction) => {
+                    writeln!(f, "{:>6}  {}", address, instruction)?;
+                    address = instruction.next_address();
+                }
+                None => {
+                    writeln!(f, "{:>6}  data {}", address, self.memory[address])?;
+                    address += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let memory = [21101, 5, -3, 7, 1105, 1, 0, 1103, 1, 9];
+        let instruction = DecodedInstruction::decode(&memory, 0).unwrap();
+        assert_eq!(instruction.to_string(), "add 5, -3, [rb+7]");
+        assert_eq!(instruction.stored_constant(), Some(2));
+        let overflow = DecodedInstruction::decode(&[1102, Int::MAX, 2, 0], 0).unwrap();
+        assert_eq!(overflow.stored_constant(), None);
+        let far = DecodedInstruction::decode(&[204, Int::MIN], 0).unwrap();
+        assert_eq!(far.to_string(), "out [rb-9223372036854775808]");
+        assert_eq!(far.read_addresses(10), vec![]);
+        assert_eq!(Operand::Relative(1).address(Int::MAX), None);
+        assert_eq!(instruction.read_addresses(10), vec![]);
+        assert_eq!(instruction.write_address(10), Some(17));
+        let compare = DecodedInstruction::decode(&[2207, 3, -1, 4], 0).unwrap();
+        assert_eq!(compare.read_addresses(10), vec![13, 9]);
+        assert_eq!(compare.write_address(10), Some(4));
+        let jump = DecodedInstruction::decode(&memory, 4).unwrap();
+        assert!(jump.is_unconditional_jump());
+        assert_eq!(jump.jump_target(), Some(0));
+        // writes to an immediate, unknown mode, leftover mode digit
+        assert_eq!(DecodedInstruction::decode(&memory, 7), None);
+        assert_eq!(DecodedInstruction::decode(&[1301, 0, 0, 0], 0), None);
+        assert_eq!(DecodedInstruction::decode(&[100104, 0], 0), None);
+        // runs off the end
+        assert_eq!(DecodedInstruction::decode(&memory, 9), None);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        // stores the return address 9 and calls the function at 10, which
+        // outputs the value after it and returns
+        let memory = [
+            109, 100, 21101, 9, 0, 0, 1105, 1, 10, 99, 4, 14, 2105, 1, 0, 42,
+        ];
+        let disassembly = Disassembly::new(&memory);
+        assert_eq!(
+            disassembly.to_string(),
+            "     0  arb 100
+     2  add 9, 0, [rb+0]
+     6  jnz 1, 10
+     9  hlt
+    10  out [14]
+    12  jnz 1, [rb+0]
+    15  data 42
+"
+        );
+        assert_eq!(disassembly.operand_owner(14).unwrap().address, 12);
+        assert_eq!(disassembly.operand_owner(15), None);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{Int, Program};
+use std::fmt;
+use std::str::FromStr;
+
+/// A condition over the machine state such as `mem[1000] > 5 && rb == 20`.
+///
+/// Supports integer literals, `ip`, `rb`, `mem[...]`, the arithmetic operators
+/// `+ - *`, comparisons, `!`, `&&` and `||`. Comparisons and logical operators
+/// evaluate to `1` or `0`, and arithmetic wraps around on overflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Literal(Int),
+    InstructionPointer,
+    RelativeBase,
+    Memory(Box<Expression>),
+    Negate(Box<Expression>),
+    Not(Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    And,
+    Or,
+}
+
+impl Expression {
+    pub fn evaluate(&self, program: &Program) -> Int {
+        use BinaryOperator::*;
+        match self {
+            Self::Literal(value) => *value,
+            Self::InstructionPointer => program.instruction_pointer() as Int,
+            Self::RelativeBase => program.relative_base(),
+            Self::Memory(address) => match address.evaluate(program) {
+                address if address < 0 => 0,
+                address => program.read_from_memory(address as usize),
+            },
+            Self::Negate(value) => value.evaluate(program).wrapping_neg(),
+            Self::Not(value) => Int::from(value.evaluate(program) == 0),
+            Self::Binary(And, a, b) => {
+                Int::from(a.evaluate(program) != 0 && b.evaluate(program) != 0)
+            }
+            Self::Binary(Or, a, b) => {
+                Int::from(a.evaluate(program) != 0 || b.evaluate(program) != 0)
+            }
+            Self::Binary(operator, a, b) => {
+                let a = a.evaluate(program);
+                let b = b.evaluate(program);
+                match operator {
+                    Add => a.wrapping_add(b),
+                    Subtract => a.wrapping_sub(b),
+                    Multiply => a.wrapping_mul(b),
+                    Equal => Int::from(a == b),
+                    NotEqual => Int::from(a != b),
+                    LessThan => Int::from(a < b),
+                    LessThanOrEqual => Int::from(a <= b),
+                    GreaterThan => Int::from(a > b),
+                    GreaterThanOrEqual => Int::from(a >= b),
+                    And | Or => unreachable!(),
+                }
+            }
+        }
+    }
+
+    pub fn is_true(&self, program: &Program) -> bool {
+        self.evaluate(program) != 0
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ParseExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, index: 0 };
+        let expression = parser.parse_or()?;
+        match parser.tokens.get(parser.index) {
+            Some(&(position, _)) => Err(ParseExpressionError::new(position, "unexpected token")),
+            None => Ok(expression),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseExpressionError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl ParseExpressionError {
+    fn new(position: usize, message: &str) -> Self {
+        Self {
+            position,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseExpressionError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(Int),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 16] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "(", ")", "[", "]",
+];
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, ParseExpressionError> {
+    let mut tokens = vec![];
+    let mut index = 0;
+    while index < s.len() {
+        let rest = &s[index..];
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            index += c.len_utf8();
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let length = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let value = rest[..length]
+                .parse()
+                .map_err(|_| ParseExpressionError::new(index, "number out of range"))?;
+            tokens.push((index, Token::Number(value)));
+            index += length;
+            continue;
+        }
+        if c.is_ascii_alphabetic() {
+            let length = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            tokens.push((index, Token::Identifier(rest[..length].to_string())));
+            index += length;
+            continue;
+        }
+        match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+            Some(symbol) => {
+                tokens.push((index, Token::Symbol(symbol)));
+                index += symbol.len();
+            }
+            None => return Err(ParseExpressionError::new(index, "unexpected character")),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+}
+
+impl Parser {
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .or_else(|| self.tokens.last())
+            .map_or(0, |&(position, _)| position)
+    }
+
+    fn next_symbol(&self) -> Option<&'static str> {
+        match self.tokens.get(self.index) {
+            Some((_, Token::Symbol(symbol))) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), ParseExpressionError> {
+        if self.next_symbol() == Some(symbol) {
+            self.index += 1;
+            Ok(())
+        } else {
+            let message = format!("expected `{}`", symbol);
+            Err(ParseExpressionError::new(self.position(), &message))
+        }
+    }
+
+    fn parse_binary(
+        &mut self,
+        operators: &[(&str, BinaryOperator)],
+        parse_operand: fn(&mut Self) -> Result<Expression, ParseExpressionError>,
+    ) -> Result<Expression, ParseExpressionError> {
+        let mut expression = parse_operand(self)?;
+        while let Some(&(_, operator)) = operators
+            .iter()
+            .find(|(symbol, _)| self.next_symbol() == Some(symbol))
+        {
+            self.index += 1;
+            let rhs = parse_operand(self)?;
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(rhs));
+        }
+        Ok(expression)
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, ParseExpressionError> {
+        self.parse_binary(&[("||", BinaryOperator::Or)], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, ParseExpressionError> {
+        self.parse_binary(&[("&&", BinaryOperator::And)], Self::parse_comparison)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expression, ParseExpressionError> {
+        use BinaryOperator::*;
+        let operators = [
+            ("==", Equal),
+            ("!=", NotEqual),
+            ("<=", LessThanOrEqual),
+            (">=", GreaterThanOrEqual),
+            ("<", LessThan),
+            (">", GreaterThan),
+        ];
+        self.parse_binary(&operators, Self::parse_sum)
+    }
+
+    fn parse_sum(&mut self) -> Result<Expression, ParseExpressionError> {
+        let operators = [("+", BinaryOperator::Add), ("-", BinaryOperator::Subtract)];
+        self.parse_binary(&operators, Self::parse_product)
+    }
+
+    fn parse_product(&mut self) -> Result<Expression, ParseExpressionError> {
+        self.parse_binary(&[("*", BinaryOperator::Multiply)], Self::parse_unary)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, ParseExpressionError> {
+        match self.next_symbol() {
+            Some("-") => {
+                self.index += 1;
+                Ok(Expression::Negate(Box::new(self.parse_unary()?)))
+            }
+            Some("!") => {
+                self.index += 1;
+                Ok(Expression::Not(Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ParseExpressionError> {
+        let position = self.position();
+        let token = self.tokens.get(self.index).map(|(_, token)| token.clone());
+        self.index += 1;
+        match token {
+            Some(Token::Number(value)) => Ok(Expression::Literal(value)),
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "ip" => Ok(Expression::InstructionPointer),
+                "rb" => Ok(Expression::RelativeBase),
+                "mem" => {
+                    self.expect("[")?;
+                    let address = self.parse_or()?;
+                    self.expect("]")?;
+                    Ok(Expression::Memory(Box::new(address)))
+                }
+                _ => Err(ParseExpressionError::new(position, "unknown identifier")),
+            },
+            Some(Token::Symbol("(")) => {
+                let expression = self.parse_or()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Some(_) => Err(ParseExpressionError::new(position, "unexpected token")),
+            None => Err(ParseExpressionError::new(
+                position,
+                "unexpected end of input",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let mut program = Program::from_input("109,20,99");
+        program.write_to_memory(1000, 7);
+        program.run(&[]);
+        let expression: Expression = "mem[1000] > 5 && rb == 20".parse().unwrap();
+        assert!(expression.is_true(&program));
+        let expression: Expression = "mem[999 + 1] * 2 - -1".parse().unwrap();
+        assert_eq!(expression.evaluate(&program), 15);
+        let expression: Expression = "!(ip == 2) || mem[0] != 109".parse().unwrap();
+        assert!(!expression.is_true(&program));
+        let expression: Expression = "mem[0] * 9223372036854775807".parse().unwrap();
+        assert_eq!(expression.evaluate(&program), Int::MAX.wrapping_mul(109));
+        let expression: Expression = "-(-9223372036854775807 - 1)".parse().unwrap();
+        assert_eq!(expression.evaluate(&program), Int::MIN);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "mem[1 > 2".parse::<Expression>().unwrap_err();
+        assert_eq!(error.message, "expected `]`");
+        let error = "ip == sp".parse::<Expression>().unwrap_err();
+        assert_eq!(error.position, 6);
+        assert!("rb $ 2".parse::<Expression>().is_err());
+        assert!("1 2".parse::<Expression>().is_err());
+    }
+}
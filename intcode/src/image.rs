@@ -0,0 +1,410 @@
+use crate::{Disassembly, Int};
+use std::fmt;
+use std::str::FromStr;
+
+/// The first bytes of a binary image. Text images never start with a NUL.
+const MAGIC: &[u8] = b"\0ICB";
+/// How many data words a listing puts on one line.
+const DATA_PER_LINE: usize = 8;
+
+/// The ways a program's memory can be stored.
+///
+/// - `Text` is the puzzle input format: values separated by commas, with any
+///   whitespace (including newlines) around them.
+/// - `Lines` has one value per line.
+/// - `Binary` is `MAGIC` followed by each value as a zigzag-encoded varint.
+/// - `Listing` is an annotated dump like the one `write` produces. Comments
+///   start with `#` or `;`, each line may begin with the address of its first
+///   value (`12:`), and values are separated by commas or whitespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Text,
+    Lines,
+    Binary,
+    Listing,
+}
+
+impl ImageFormat {
+    /// Guesses the format of `bytes`. Anything that isn't binary and doesn't
+    /// use listing syntax is read as text if it has a comma, and as lines
+    /// otherwise.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(MAGIC) {
+            Self::Binary
+        } else if bytes.iter().any(|b| b"#;:".contains(b)) {
+            Self::Listing
+        } else if bytes.contains(&b',') {
+            Self::Text
+        } else {
+            Self::Lines
+        }
+    }
+
+    pub fn read(self, bytes: &[u8]) -> Result<Vec<Int>, ImageError> {
+        if self == Self::Binary {
+            return read_binary(bytes);
+        }
+        let text = match std::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => {
+                let line = bytes[..e.valid_up_to()].split(|&b| b == b'\n').count();
+                return Err(ImageError::InvalidUtf8 { line });
+            }
+        };
+        match self {
+            Self::Text => read_text(text),
+            Self::Lines => read_lines(text),
+            Self::Listing => read_listing(text),
+            Self::Binary => unreachable!(),
+        }
+    }
+
+    pub fn write(self, memory: &[Int]) -> Vec<u8> {
+        match self {
+            Self::Text => {
+                let values: Vec<_> = memory.iter().map(Int::to_string).collect();
+                format!("{}\n", values.join(",")).into_bytes()
+            }
+            Self::Lines => memory
+                .iter()
+                .map(|value| format!("{}\n", value))
+                .collect::<String>()
+                .into_bytes(),
+            Self::Binary => write_binary(memory),
+            Self::Listing => write_listing(memory).into_bytes(),
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "lines" => Ok(Self::Lines),
+            "binary" => Ok(Self::Binary),
+            "listing" => Ok(Self::Listing),
+            _ => Err(format!("unknown image format `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Text => "text",
+            Self::Lines => "lines",
+            Self::Binary => "binary",
+            Self::Listing => "listing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Reads an image in any format, detecting which one it is.
+pub fn read_image(bytes: &[u8]) -> Result<Vec<Int>, ImageError> {
+    ImageFormat::detect(bytes).read(bytes)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// A token that isn't a value, or an empty one between two commas.
+    InvalidValue {
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    /// A listing line labelled with a different address than where its
+    /// first value ends up.
+    WrongAddress {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidUtf8 {
+        line: usize,
+    },
+    /// A binary image whose last varint is cut off.
+    Truncated {
+        offset: usize,
+    },
+    /// A binary varint that doesn't fit in an `Int`.
+    Overflow {
+        offset: usize,
+    },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidValue {
+                line,
+                column,
+                token,
+            } if token.is_empty() => write!(f, "line {}, column {}: missing value", line, column),
+            Self::InvalidValue {
+                line,
+                column,
+                token,
+            } => write!(
+                f,
+                "line {}, column {}: invalid value `{}`",
+                line, column, token
+            ),
+            Self::WrongAddress {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: labelled address {} but the next address is {}",
+                line, found, expected
+            ),
+            Self::InvalidUtf8 { line } => write!(f, "line {}: invalid UTF-8", line),
+            Self::Truncated { offset } => write!(f, "byte {}: truncated value", offset),
+            Self::Overflow { offset } => write!(f, "byte {}: value is too large", offset),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+fn parse_value(token: &str, line: usize, column: usize) -> Result<Int, ImageError> {
+    token.parse().map_err(|_| ImageError::InvalidValue {
+        line,
+        column,
+        token: token.to_string(),
+    })
+}
+
+/// Splits `text` on `separator`, yielding each token trimmed along with the
+/// byte offset it starts at.
+fn tokens(text: &str, separator: impl Fn(char) -> bool) -> Vec<(usize, &str)> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ','))) {
+        if i == text.len() || separator(c) {
+            let token = &text[start..i];
+            let leading = token.len() - token.trim_start().len();
+            tokens.push((start + leading, token.trim()));
+            start = i + c.len_utf8();
+        }
+    }
+    tokens
+}
+
+fn read_text(text: &str) -> Result<Vec<Int>, ImageError> {
+    let mut memory = vec![];
+    // values may wrap onto the next line, so lines are counted up to the
+    // start of each token
+    let (mut line, mut line_start, mut scanned) = (1, 0, 0);
+    for (offset, token) in tokens(text.trim_end(), |c| c == ',') {
+        for (i, c) in text[scanned..offset].char_indices() {
+            if c == '\n' {
+                line += 1;
+                line_start = scanned + i + 1;
+            }
+        }
+        scanned = offset;
+        memory.push(parse_value(token, line, offset - line_start + 1)?);
+    }
+    Ok(memory)
+}
+
+fn read_lines(text: &str) -> Result<Vec<Int>, ImageError> {
+    let mut memory = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let token = line.trim();
+        if !token.is_empty() {
+            let column = line.len() - line.trim_start().len() + 1;
+            memory.push(parse_value(token, i + 1, column)?);
+        }
+    }
+    Ok(memory)
+}
+
+fn read_listing(text: &str) -> Result<Vec<Int>, ImageError> {
+    let mut memory = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let code = match line.find(['#', ';']) {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let (values, values_start) = match code.find(':') {
+            Some(colon) => {
+                let label = code[..colon].trim();
+                let column = code.len() - code.trim_start().len() + 1;
+                let found = label.parse().map_err(|_| ImageError::InvalidValue {
+                    line: line_number,
+                    column,
+                    token: label.to_string(),
+                })?;
+                if found != memory.len() {
+                    return Err(ImageError::WrongAddress {
+                        line: line_number,
+                        expected: memory.len(),
+                        found,
+                    });
+                }
+                (&code[colon + 1..], colon + 1)
+            }
+            None => (code, 0),
+        };
+        let separator = |c: char| c == ',' || c.is_whitespace();
+        for (offset, token) in tokens(values, separator) {
+            if !token.is_empty() {
+                memory.push(parse_value(token, line_number, values_start + offset + 1)?);
+            }
+        }
+    }
+    Ok(memory)
+}
+
+/// Lists the program one instruction per line, with runs of data in rows of
+/// `DATA_PER_LINE`, each commented with what it disassembles to.
+fn write_listing(memory: &[Int]) -> String {
+    let disassembly = Disassembly::new(memory);
+    let mut listing = String::new();
+    let mut address = 0;
+    while address < memory.len() {
+        let (end, comment) = match disassembly.instruction_at(address) {
+            Some(instruction) => (instruction.next_address(), instruction.to_string()),
+            None => {
+                let mut end = address + 1;
+                while end < memory.len()
+                    && end - address < DATA_PER_LINE
+                    && disassembly.instruction_at(end).is_none()
+                {
+                    end += 1;
+                }
+                (end, String::from("data"))
+            }
+        };
+        let values: Vec<_> = memory[address..end].iter().map(Int::to_string).collect();
+        let values = values.join(", ");
+        listing += &format!("{:>6}: {:<32} # {}\n", address, values, comment);
+        address = end;
+    }
+    listing
+}
+
+fn write_binary(memory: &[Int]) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    for &value in memory {
+        let mut zigzag = ((value << 1) ^ (value >> (Int::BITS - 1))) as u64;
+        while zigzag >= 0x80 {
+            bytes.push(zigzag as u8 | 0x80);
+            zigzag >>= 7;
+        }
+        bytes.push(zigzag as u8);
+    }
+    bytes
+}
+
+fn read_binary(bytes: &[u8]) -> Result<Vec<Int>, ImageError> {
+    let mut memory = vec![];
+    let mut offset = MAGIC.len();
+    while offset < bytes.len() {
+        let start = offset;
+        let mut zigzag = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = *bytes
+                .get(offset)
+                .ok_or(ImageError::Truncated { offset: start })?;
+            offset += 1;
+            if shift >= 64 || (shift == 63 && byte & 0x7e != 0) {
+                return Err(ImageError::Overflow { offset: start });
+            }
+            zigzag |= u64::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        memory.push((zigzag >> 1) as Int ^ -((zigzag & 1) as Int));
+    }
+    Ok(memory)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROGRAM: [Int; 12] = [1, 9, 10, 3, 2, 3, 11, 0, 99, 30, -40, 1 << 40];
+
+    #[test]
+    fn test_round_trip() {
+        let formats = [
+            ImageFormat::Text,
+            ImageFormat::Lines,
+            ImageFormat::Binary,
+            ImageFormat::Listing,
+        ];
+        for &format in &formats {
+            let bytes = format.write(&PROGRAM);
+            assert_eq!(ImageFormat::detect(&bytes), format);
+            assert_eq!(read_image(&bytes), Ok(PROGRAM.to_vec()), "{}", format);
+        }
+        let extremes = [Int::MIN, -1, 0, Int::MAX];
+        let bytes = ImageFormat::Binary.write(&extremes);
+        assert_eq!(read_image(&bytes), Ok(extremes.to_vec()));
+    }
+
+    #[test]
+    fn test_listing() {
+        let listing = "
+            # adds two numbers
+            0: 1, 9, 10, 3   ; add
+            4: 2 3 11 0
+            99               # halt
+            9: 30, 40
+        ";
+        let memory = read_image(listing.as_bytes()).unwrap();
+        assert_eq!(memory, vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40]);
+        assert_eq!(
+            ImageFormat::Listing.write(&memory[..4]),
+            format!("{:>6}: {:<32} # add [9], [10], [3]\n", 0, "1, 9, 10, 3").into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            read_image(b"1,2,\n3,x4,5").unwrap_err().to_string(),
+            "line 2, column 3: invalid value `x4`"
+        );
+        assert_eq!(
+            read_image(b"1,,2").unwrap_err().to_string(),
+            "line 1, column 3: missing value"
+        );
+        assert_eq!(
+            read_image(b"1\n2\n  3.5\n").unwrap_err().to_string(),
+            "line 3, column 3: invalid value `3.5`"
+        );
+        assert_eq!(
+            read_image(b"0: 1 2\n3: 4").unwrap_err(),
+            ImageError::WrongAddress {
+                line: 2,
+                expected: 2,
+                found: 3
+            }
+        );
+        assert_eq!(
+            read_image(b"0: 1 2 # ok\n2: 4, five")
+                .unwrap_err()
+                .to_string(),
+            "line 2, column 7: invalid value `five`"
+        );
+        assert_eq!(
+            read_image(b"\0ICB\x02\x80"),
+            Err(ImageError::Truncated { offset: 5 })
+        );
+        assert_eq!(
+            read_image(b"\0ICB\xff\xff\xff\xff\xff\xff\xff\xff\xff\x7f"),
+            Err(ImageError::Overflow { offset: 4 })
+        );
+    }
+}
@@ -1,4 +1,38 @@
 use std::fmt;
+use std::sync::Arc;
+
+mod asynchronous;
+mod compiler;
+mod debug;
+mod decompiler;
+mod disasm;
+mod expression;
+mod image;
+mod linker;
+mod memory;
+mod opcodes;
+mod optimizer;
+mod patch;
+mod session;
+mod state;
+mod threaded;
+
+pub use asynchronous::{channel, AsyncProgram, AsyncReceiver, AsyncSender, Recv};
+pub use compiler::{compile, CompileError};
+pub use debug::{Breakpoint, Debugger};
+pub use decompiler::decompile;
+pub use disasm::{DecodedInstruction, Disassembly, Operand, Operation};
+pub use expression::{Expression, ParseExpressionError};
+pub use image::{read_image, ImageError, ImageFormat};
+pub use linker::{link, Import, LinkError, Object, ParseObjectError};
+use memory::Memory;
+pub use memory::MemoryStats;
+pub use opcodes::OpcodeRegistry;
+pub use optimizer::{optimize, Op, OptimizedProgram, OptimizerStats};
+pub use patch::{Patch, PatchEntry, PatchError};
+pub use session::{Divergence, Event, Observation, ParseSessionError, Recorder, Session};
+pub use state::InfiniteLoop;
+pub use threaded::{ProgramHandle, ThreadError};
 
 pub type Int = i64;
 
@@ -7,73 +41,205 @@ pub struct Program {
     state: ProgramState,
     instruction_pointer: usize,
     relative_base: Int,
-    memory: Vec<Int>,
+    memory: Memory,
     inputs: Vec<Int>,
     outputs: Vec<Int>,
+    opcodes: Arc<OpcodeRegistry>,
+    steps: u64,
 }
 
 impl Program {
+    /// Loads a program from text in any of the `ImageFormat`s, panicking if
+    /// it's invalid.
     pub fn from_input(input: &str) -> Self {
-        let memory = input
-            .trim()
-            .split(",")
-            .map(|s| s.parse().unwrap())
-            .collect();
+        Self::from_image(input.as_bytes()).unwrap_or_else(|e| panic!("invalid program: {}", e))
+    }
+
+    /// Loads a program in any of the `ImageFormat`s.
+    pub fn from_image(bytes: &[u8]) -> Result<Self, ImageError> {
+        read_image(bytes).map(Self::from_memory)
+    }
+
+    pub fn from_memory(memory: Vec<Int>) -> Self {
         Self {
             state: ProgramState::InitialState,
             instruction_pointer: 0,
             relative_base: 0,
-            memory,
+            memory: Memory::from_values(memory),
             inputs: vec![],
             outputs: vec![],
+            opcodes: Arc::default(),
+            steps: 0,
         }
     }
 
+    /// Extends the instruction set with the custom opcodes in `opcodes`.
+    pub fn with_opcodes(mut self, opcodes: OpcodeRegistry) -> Self {
+        self.opcodes = Arc::new(opcodes);
+        self
+    }
+
     pub fn is_finished(&self) -> bool {
         self.state == ProgramState::Halted
     }
 
+    pub fn state(&self) -> ProgramState {
+        self.state
+    }
+
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    pub fn relative_base(&self) -> Int {
+        self.relative_base
+    }
+
+    /// Number of instructions executed so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn set_instruction_pointer(&mut self, address: usize) {
+        self.instruction_pointer = address;
+    }
+
+    pub fn set_relative_base(&mut self, relative_base: Int) {
+        self.relative_base = relative_base;
+    }
+
+    /// Takes the next pending input, if any.
+    pub fn pop_input(&mut self) -> Option<Int> {
+        if self.inputs.is_empty() {
+            None
+        } else {
+            Some(self.inputs.remove(0))
+        }
+    }
+
+    pub fn push_output(&mut self, value: Int) {
+        self.outputs.push(value);
+    }
+
+    /// Queues an input, letting a program waiting for one continue.
+    pub fn push_input(&mut self, value: Int) {
+        self.inputs.push(value);
+        if self.state == ProgramState::WaitingForInput {
+            self.state = ProgramState::Running;
+        }
+    }
+
+    /// Removes and returns the outputs produced since the last call to
+    /// `run` or `take_outputs`.
+    pub fn take_outputs(&mut self) -> Vec<Int> {
+        std::mem::take(&mut self.outputs)
+    }
+
     pub fn run(&mut self, inputs: &[Int]) -> Vec<Int> {
         self.outputs.clear();
-        self.inputs = inputs.to_vec();
+        self.inputs.extend_from_slice(inputs);
         self.state = ProgramState::Running;
         while self.state == ProgramState::Running {
-            let opcode = self.memory[self.instruction_pointer];
-            let instruction = self.parse_instruction(opcode);
-            instruction.run(self);
+            self.step();
         }
         self.outputs.clone()
     }
 
+    /// Moves the program onto its own thread, communicating over channels.
+    pub fn spawn(self) -> ProgramHandle {
+        ProgramHandle::spawn(self)
+    }
+
+    /// Executes the instruction at the instruction pointer.
+    pub fn step(&mut self) {
+        let opcode = self.read_from_memory(self.instruction_pointer);
+        let instruction = self.parse_instruction(opcode);
+        instruction.run(self);
+        if self.state != ProgramState::WaitingForInput {
+            self.steps += 1;
+        }
+    }
+
+    fn opcode(&self) -> Int {
+        self.read_from_memory(self.instruction_pointer) % 100
+    }
+
     pub fn read_from_memory(&self, address: usize) -> Int {
-        self.memory.get(address).copied().unwrap_or(0)
+        self.memory.read(address)
+    }
+
+    /// Decodes the standard instruction at `address`, if there is one.
+    pub fn decode_at(&self, address: usize) -> Option<DecodedInstruction> {
+        let words: Vec<_> = (address..address + 4)
+            .map(|a| self.read_from_memory(a))
+            .collect();
+        let mut instruction = DecodedInstruction::decode(&words, 0)?;
+        instruction.address = address;
+        Some(instruction)
     }
 
     pub fn write_to_memory(&mut self, address: usize, value: Int) {
-        if address >= self.memory.len() {
-            self.memory.resize(address + 1, 0);
+        self.memory.write(address, value);
+    }
+
+    /// Copies out memory up to the highest address loaded or written.
+    pub fn memory(&self) -> Vec<Int> {
+        self.memory.to_vec()
+    }
+
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.memory.stats()
+    }
+
+    /// Applies every entry of `patch`, leaving memory untouched if any
+    /// expected value does not match.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), PatchError> {
+        for entry in patch.entries() {
+            let found = self.read_from_memory(entry.address);
+            match entry.expected {
+                Some(expected) if expected != found => {
+                    return Err(PatchError::Mismatch {
+                        address: entry.address,
+                        expected,
+                        found,
+                    })
+                }
+                _ => {}
+            }
+        }
+        for entry in patch.entries() {
+            self.write_to_memory(entry.address, entry.value);
         }
-        self.memory[address] = value;
+        Ok(())
     }
 
     fn parse_instruction(&self, opcode: Int) -> Box<dyn Instruction> {
         let ip = self.instruction_pointer;
+        let words = [
+            opcode,
+            self.read_from_memory(ip + 1),
+            self.read_from_memory(ip + 2),
+            self.read_from_memory(ip + 3),
+        ];
         match opcode % 100 {
-            1 => Box::new(Add::new(&self.memory[ip..ip + 4])),
-            2 => Box::new(Mult::new(&self.memory[ip..ip + 4])),
-            3 => Box::new(Input::new(&self.memory[ip..ip + 2])),
-            4 => Box::new(Output::new(&self.memory[ip..ip + 2])),
-            5 => Box::new(JumpIfTrue::new(&self.memory[ip..ip + 3])),
-            6 => Box::new(JumpIfFalse::new(&self.memory[ip..ip + 3])),
-            7 => Box::new(LessThan::new(&self.memory[ip..ip + 4])),
-            8 => Box::new(Equals::new(&self.memory[ip..ip + 4])),
-            9 => Box::new(RelativeBaseOffset::new(&self.memory[ip..ip + 2])),
+            1 => Box::new(Add::new(&words)),
+            2 => Box::new(Mult::new(&words)),
+            3 => Box::new(Input::new(&words)),
+            4 => Box::new(Output::new(&words)),
+            5 => Box::new(JumpIfTrue::new(&words)),
+            6 => Box::new(JumpIfFalse::new(&words)),
+            7 => Box::new(LessThan::new(&words)),
+            8 => Box::new(Equals::new(&words)),
+            9 => Box::new(RelativeBaseOffset::new(&words)),
             99 => Box::new(Halt),
-            _ => panic!("Invalid operation"),
+            opcode => self
+                .opcodes
+                .decode(opcode, &self.memory, ip)
+                .unwrap_or_else(|| panic!("Invalid operation")),
         }
     }
 
-    fn evaluate(&self, parameter: Parameter) -> Int {
+    pub fn evaluate(&self, parameter: Parameter) -> Int {
         use ParameterMode::*;
         let Parameter { parameter, mode } = parameter;
         match mode {
@@ -86,7 +252,7 @@ impl Program {
         }
     }
 
-    fn evaluate_address(&self, parameter: Parameter) -> usize {
+    pub fn evaluate_address(&self, parameter: Parameter) -> usize {
         use ParameterMode::*;
         let Parameter { parameter, mode } = parameter;
         let address = match mode {
@@ -98,15 +264,18 @@ impl Program {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ProgramState {
     InitialState,
     Running,
     WaitingForInput,
+    Paused,
     Halted,
 }
 
-trait Instruction: fmt::Debug {
+/// An executable instruction. Implementations are responsible for moving the
+/// instruction pointer past themselves.
+pub trait Instruction: fmt::Debug {
     fn run(&self, program: &mut Program);
 }
 
@@ -342,13 +511,13 @@ impl Instruction for RelativeBaseOffset {
 }
 
 #[derive(Debug, Clone, Copy)]
-struct Parameter {
-    parameter: Int,
-    mode: ParameterMode,
+pub struct Parameter {
+    pub parameter: Int,
+    pub mode: ParameterMode,
 }
 
 impl Parameter {
-    fn from_instruction(instruction: &[Int], count: usize) -> Vec<Self> {
+    pub fn from_instruction(instruction: &[Int], count: usize) -> Vec<Self> {
         let modes = ParameterMode::from_opcode(instruction[0], count);
         let parameters = &instruction[1..count + 1];
         parameters
@@ -359,8 +528,8 @@ impl Parameter {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum ParameterMode {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterMode {
     Position,
     Immediate,
     Relative,
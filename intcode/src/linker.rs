@@ -0,0 +1,409 @@
+use crate::Int;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A module of Intcode that can be placed anywhere in memory by `link`.
+///
+/// Words listed as relocations hold addresses inside the module and are
+/// shifted by the address the module is placed at. Imported words have the
+/// address of a symbol exported by some module added to them. The text
+/// format has one directive per line, with `code` lines concatenated. Blank
+/// lines and anything after a `#` are ignored:
+///
+/// ```text
+/// module main
+/// code 3,0,1101,0,9,0
+/// code 1105,1,0,99
+/// export main 0
+/// relocate 4
+/// import 1 arg
+/// import 5 ret
+/// import 8 print_twice
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Object {
+    pub name: String,
+    pub code: Vec<Int>,
+    pub exports: BTreeMap<String, usize>,
+    pub relocations: Vec<usize>,
+    pub imports: Vec<Import>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub offset: usize,
+    pub symbol: String,
+}
+
+impl Object {
+    pub fn new(name: &str, code: Vec<Int>) -> Self {
+        Self {
+            name: name.to_string(),
+            code,
+            ..Self::default()
+        }
+    }
+
+    /// Exports `symbol` at `offset`, failing if the module already exports
+    /// a symbol with that name.
+    pub fn export(&mut self, symbol: &str, offset: usize) -> Result<(), LinkError> {
+        if self.exports.contains_key(symbol) {
+            return Err(LinkError::DuplicateExport {
+                symbol: symbol.to_string(),
+                module: self.name.clone(),
+            });
+        }
+        self.exports.insert(symbol.to_string(), offset);
+        Ok(())
+    }
+
+    pub fn relocate(&mut self, offset: usize) {
+        self.relocations.push(offset);
+    }
+
+    pub fn import(&mut self, offset: usize, symbol: &str) {
+        self.imports.push(Import {
+            offset,
+            symbol: symbol.to_string(),
+        });
+    }
+}
+
+/// Places `objects` one after another, starting at address 0, and resolves
+/// their relocations and imports into a single image. The program starts
+/// with the first object's code.
+pub fn link(objects: &[Object]) -> Result<Vec<Int>, LinkError> {
+    let mut bases = Vec::with_capacity(objects.len());
+    let mut symbols: HashMap<&str, (usize, &str)> = HashMap::new();
+    let mut image = vec![];
+    for object in objects {
+        let base = image.len();
+        for (symbol, &offset) in &object.exports {
+            if offset > object.code.len() {
+                return Err(LinkError::OutOfRange {
+                    module: object.name.clone(),
+                    offset,
+                });
+            }
+            if let Some((_, first)) = symbols.insert(symbol, (base + offset, &object.name)) {
+                return Err(LinkError::DuplicateSymbol {
+                    symbol: symbol.clone(),
+                    first: first.to_string(),
+                    second: object.name.clone(),
+                });
+            }
+        }
+        bases.push(base);
+        image.extend_from_slice(&object.code);
+    }
+    for (object, &base) in objects.iter().zip(&bases) {
+        let mut add = |offset: usize, value: usize| {
+            if offset >= object.code.len() {
+                return Err(LinkError::OutOfRange {
+                    module: object.name.clone(),
+                    offset,
+                });
+            }
+            let word = &mut image[base + offset];
+            *word = Int::try_from(value)
+                .ok()
+                .and_then(|value| word.checked_add(value))
+                .ok_or_else(|| LinkError::Overflow {
+                    module: object.name.clone(),
+                    offset,
+                })?;
+            Ok(())
+        };
+        for &offset in &object.relocations {
+            add(offset, base)?;
+        }
+        for import in &object.imports {
+            let address = match symbols.get(import.symbol.as_str()) {
+                Some(&(address, _)) => address,
+                None => {
+                    return Err(LinkError::UndefinedSymbol {
+                        symbol: import.symbol.clone(),
+                        module: object.name.clone(),
+                    })
+                }
+            };
+            add(import.offset, address)?;
+        }
+    }
+    Ok(image)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    UndefinedSymbol {
+        symbol: String,
+        module: String,
+    },
+    DuplicateSymbol {
+        symbol: String,
+        first: String,
+        second: String,
+    },
+    DuplicateExport {
+        symbol: String,
+        module: String,
+    },
+    /// An export, relocation or import outside of its module's code.
+    OutOfRange {
+        module: String,
+        offset: usize,
+    },
+    /// A relocated or imported word that no longer fits in an `Int`.
+    Overflow {
+        module: String,
+        offset: usize,
+    },
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UndefinedSymbol { symbol, module } => {
+                write!(f, "undefined symbol `{}` imported by {}", symbol, module)
+            }
+            Self::DuplicateSymbol {
+                symbol,
+                first,
+                second,
+            } => write!(
+                f,
+                "symbol `{}` is exported by both {} and {}",
+                symbol, first, second
+            ),
+            Self::DuplicateExport { symbol, module } => {
+                write!(f, "symbol `{}` is exported twice by {}", symbol, module)
+            }
+            Self::OutOfRange { module, offset } => {
+                write!(f, "offset {} is outside of module {}", offset, module)
+            }
+            Self::Overflow { module, offset } => {
+                write!(
+                    f,
+                    "word {} of module {} overflows when linked",
+                    offset, module
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl FromStr for Object {
+    type Err = ParseObjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut object = Self::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            parse_directive(&mut object, line).map_err(|message| ParseObjectError {
+                line: i + 1,
+                message,
+            })?;
+        }
+        if object.name.is_empty() {
+            return Err(ParseObjectError {
+                line: 1,
+                message: String::from("missing `module` directive"),
+            });
+        }
+        Ok(object)
+    }
+}
+
+fn parse_directive(object: &mut Object, line: &str) -> Result<(), String> {
+    let (directive, rest) = match line.find(char::is_whitespace) {
+        Some(i) => (&line[..i], line[i..].trim()),
+        None => (line, ""),
+    };
+    let words: Vec<_> = rest.split_whitespace().collect();
+    let offset = |word: &str| {
+        word.parse::<usize>()
+            .map_err(|_| format!("invalid offset `{}`", word))
+    };
+    match (directive, words.as_slice()) {
+        ("module", [name]) if object.name.is_empty() => object.name = name.to_string(),
+        ("module", [_]) => return Err(String::from("duplicate `module` directive")),
+        ("code", _) => {
+            for word in rest.split(',').map(str::trim) {
+                let value = word
+                    .parse()
+                    .map_err(|_| format!("invalid value `{}`", word))?;
+                object.code.push(value);
+            }
+        }
+        ("export", [symbol, word]) => object
+            .export(symbol, offset(word)?)
+            .map_err(|_| format!("symbol `{}` is exported twice", symbol))?,
+        ("relocate", [_, ..]) => {
+            for word in words {
+                object.relocate(offset(word)?);
+            }
+        }
+        ("import", [word, symbol]) => object.import(offset(word)?, symbol),
+        ("module", _) => return Err(String::from("expected `module NAME`")),
+        ("export", _) => return Err(String::from("expected `export SYMBOL OFFSET`")),
+        ("relocate", _) => return Err(String::from("expected `relocate OFFSET...`")),
+        ("import", _) => return Err(String::from("expected `import OFFSET SYMBOL`")),
+        _ => return Err(format!("unknown directive `{}`", directive)),
+    }
+    Ok(())
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "module {}", self.name)?;
+        for chunk in self.code.chunks(16) {
+            let values: Vec<_> = chunk.iter().map(Int::to_string).collect();
+            writeln!(f, "code {}", values.join(","))?;
+        }
+        for (symbol, offset) in &self.exports {
+            writeln!(f, "export {} {}", symbol, offset)?;
+        }
+        if !self.relocations.is_empty() {
+            let offsets: Vec<_> = self.relocations.iter().map(usize::to_string).collect();
+            writeln!(f, "relocate {}", offsets.join(" "))?;
+        }
+        for import in &self.imports {
+            writeln!(f, "import {} {}", import.offset, import.symbol)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseObjectError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseObjectError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Program;
+
+    // Reads a value and calls `print_twice` from another module.
+    const MAIN: &str = "
+        module main
+        code 3,0,1101,0,9,0   # read into arg, set ret to 9
+        code 1105,1,0,99      # call print_twice, halt
+        export main 0
+        relocate 4
+        import 1 arg
+        import 5 ret
+        import 8 print_twice
+    ";
+
+    // Outputs `arg` twice and jumps back to `ret`.
+    const LIBRARY: &str = "
+        module library
+        code 4,7,4,7,106,0,8
+        code 0,0              # arg, ret
+        export print_twice 0
+        export arg 7
+        export ret 8
+        relocate 1 3 6
+    ";
+
+    #[test]
+    fn test_parse() {
+        let object: Object = MAIN.parse().unwrap();
+        assert_eq!(object.name, "main");
+        assert_eq!(object.code, vec![3, 0, 1101, 0, 9, 0, 1105, 1, 0, 99]);
+        assert_eq!(object.relocations, vec![4]);
+        assert_eq!(object.imports.len(), 3);
+        assert_eq!(object.to_string().parse(), Ok(object));
+    }
+
+    #[test]
+    fn test_link() {
+        let main = MAIN.parse().unwrap();
+        let library = LIBRARY.parse().unwrap();
+        let image = link(&[main, library]).unwrap();
+        assert_eq!(
+            image,
+            vec![3, 17, 1101, 0, 9, 18, 1105, 1, 10, 99, 4, 17, 4, 17, 106, 0, 18, 0, 0]
+        );
+        let mut program = Program::from_memory(image);
+        assert_eq!(program.run(&[21]), vec![21, 21]);
+        assert!(program.is_finished());
+    }
+
+    #[test]
+    fn test_link_errors() {
+        let main: Object = MAIN.parse().unwrap();
+        let library: Object = LIBRARY.parse().unwrap();
+        assert_eq!(
+            link(std::slice::from_ref(&main)),
+            Err(LinkError::UndefinedSymbol {
+                symbol: String::from("arg"),
+                module: String::from("main"),
+            })
+        );
+
+        let mut other = Object::new("other", vec![99]);
+        other.export("arg", 0).unwrap();
+        assert_eq!(
+            other.export("arg", 0),
+            Err(LinkError::DuplicateExport {
+                symbol: String::from("arg"),
+                module: String::from("other"),
+            })
+        );
+        let error = link(&[main.clone(), library.clone(), other]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "symbol `arg` is exported by both library and other"
+        );
+
+        let mut huge = Object::new("huge", vec![Int::MAX, 99]);
+        huge.relocate(0);
+        assert_eq!(
+            link(&[Object::new("first", vec![99]), huge]),
+            Err(LinkError::Overflow {
+                module: String::from("huge"),
+                offset: 0,
+            })
+        );
+
+        let mut broken = library;
+        broken.relocate(9);
+        assert_eq!(
+            link(&[main, broken]),
+            Err(LinkError::OutOfRange {
+                module: String::from("library"),
+                offset: 9,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "module a\ncode 1,x".parse::<Object>().unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.message, "invalid value `x`");
+        let error = "module a\nexport b 0\nexport b 1".parse::<Object>();
+        assert_eq!(error.unwrap_err().line, 3);
+        assert!("code 99".parse::<Object>().is_err());
+        assert!("module a\nimport 1".parse::<Object>().is_err());
+        assert!("module a\nlink 1".parse::<Object>().is_err());
+    }
+}
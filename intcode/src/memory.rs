@@ -0,0 +1,206 @@
+use crate::Int;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const PAGE_SIZE: usize = 256;
+
+type Page = [Int; PAGE_SIZE];
+
+/// Paged program memory shared copy-on-write between clones.
+///
+/// Cloning only bumps a reference count. The page table and individual pages
+/// are copied on the first write after a clone, so forks pay only for the
+/// pages they modify. Pages that were never written are not allocated.
+#[derive(Clone)]
+pub(crate) struct Memory {
+    pages: Arc<Vec<Option<Arc<Page>>>>,
+    // one past the highest address that was loaded or written
+    len: usize,
+    stats: Arc<CopyCounters>,
+}
+
+#[derive(Debug, Default)]
+struct CopyCounters {
+    pages: AtomicUsize,
+    bytes: AtomicUsize,
+}
+
+/// Copying done by copy-on-write memory, totalled over a program and every
+/// program cloned from it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub pages_copied: usize,
+    pub bytes_copied: usize,
+}
+
+impl Memory {
+    pub(crate) fn from_values(values: Vec<Int>) -> Self {
+        let pages = values
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = [0; PAGE_SIZE];
+                page[..chunk.len()].copy_from_slice(chunk);
+                Some(Arc::new(page))
+            })
+            .collect();
+        Self {
+            pages: Arc::new(pages),
+            len: values.len(),
+            stats: Arc::default(),
+        }
+    }
+
+    pub(crate) fn read(&self, address: usize) -> Int {
+        match self.pages.get(address / PAGE_SIZE) {
+            Some(Some(page)) => page[address % PAGE_SIZE],
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn write(&mut self, address: usize, value: Int) {
+        let index = address / PAGE_SIZE;
+        if Arc::strong_count(&self.pages) > 1 {
+            let table_size = self.pages.len() * mem::size_of::<Option<Arc<Page>>>();
+            self.stats.bytes.fetch_add(table_size, Ordering::Relaxed);
+        }
+        let pages = Arc::make_mut(&mut self.pages);
+        if index >= pages.len() {
+            pages.resize(index + 1, None);
+        }
+        let page = pages[index].get_or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+        if Arc::strong_count(page) > 1 {
+            self.stats.pages.fetch_add(1, Ordering::Relaxed);
+            let page_size = mem::size_of::<Page>();
+            self.stats.bytes.fetch_add(page_size, Ordering::Relaxed);
+        }
+        Arc::make_mut(page)[address % PAGE_SIZE] = value;
+        self.len = self.len.max(address + 1);
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<Int> {
+        (0..self.len).map(|address| self.read(address)).collect()
+    }
+
+    /// Number of cells up to and including the last non-zero one.
+    fn trimmed_len(&self) -> usize {
+        for (index, page) in self.pages.iter().enumerate().rev() {
+            if let Some(page) = page {
+                if let Some(offset) = page.iter().rposition(|&value| value != 0) {
+                    return index * PAGE_SIZE + offset + 1;
+                }
+            }
+        }
+        0
+    }
+
+    pub(crate) fn stats(&self) -> MemoryStats {
+        MemoryStats {
+            pages_copied: self.stats.pages.load(Ordering::Relaxed),
+            bytes_copied: self.stats.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Memory is compared by the values a program can read, so cells that were
+/// never written compare equal to cells holding zero. Pages still shared
+/// between forks are not compared value by value.
+impl PartialEq for Memory {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.pages, &other.pages) {
+            return true;
+        }
+        let page_count = self.pages.len().max(other.pages.len());
+        (0..page_count).all(|index| {
+            let a = self.pages.get(index).and_then(Option::as_ref);
+            let b = other.pages.get(index).and_then(Option::as_ref);
+            match (a, b) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b) || a[..] == b[..],
+                (Some(page), None) | (None, Some(page)) => page.iter().all(|&v| v == 0),
+                (None, None) => true,
+            }
+        })
+    }
+}
+
+impl Eq for Memory {}
+
+impl Hash for Memory {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let len = self.trimmed_len();
+        len.hash(state);
+        for address in 0..len {
+            self.read(address).hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.to_vec()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_write() {
+        let mut memory = Memory::from_values((0..300).collect());
+        assert_eq!(memory.len, 300);
+        assert_eq!(memory.read(299), 299);
+        assert_eq!(memory.read(300), 0);
+        memory.write(1000, 7);
+        assert_eq!(memory.read(1000), 7);
+        assert_eq!(memory.read(999), 0);
+        assert_eq!(memory.len, 1001);
+        assert_eq!(memory.pages.iter().filter(|p| p.is_some()).count(), 3);
+        assert_eq!(memory.stats(), MemoryStats::default());
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let memory = Memory::from_values((0..1024).collect());
+        let mut fork = memory.clone();
+        assert!(Arc::ptr_eq(&memory.pages, &fork.pages));
+        fork.write(5, -5);
+        fork.write(6, -6);
+        assert_eq!(memory.read(5), 5);
+        assert_eq!(fork.read(5), -5);
+        let stats = fork.stats();
+        assert_eq!(stats.pages_copied, 1);
+        let table_size = 4 * mem::size_of::<Option<Arc<Page>>>();
+        assert_eq!(stats.bytes_copied, mem::size_of::<Page>() + table_size);
+        assert_eq!(memory.stats(), stats);
+
+        let shared_pages = memory.pages.iter().zip(fork.pages.iter());
+        let shared_count = shared_pages
+            .filter(|(a, b)| Arc::ptr_eq(a.as_ref().unwrap(), b.as_ref().unwrap()))
+            .count();
+        assert_eq!(shared_count, 3);
+    }
+
+    #[test]
+    fn test_equality() {
+        let memory = Memory::from_values(vec![1, 2, 3]);
+        let mut other = memory.clone();
+        assert_eq!(memory, other);
+        other.write(2000, 0);
+        assert_eq!(memory, other);
+        assert_eq!(hash(&memory), hash(&other));
+        other.write(1, 5);
+        assert_ne!(memory, other);
+        other.write(1, 2);
+        assert_eq!(memory, other);
+        assert_eq!(hash(&memory), hash(&other));
+    }
+
+    fn hash(memory: &Memory) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        memory.hash(&mut hasher);
+        hasher.finish()
+    }
+}
@@ -0,0 +1,156 @@
+use crate::memory::Memory;
+use crate::{Instruction, Int, Parameter};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+const BUILTIN_OPCODES: [Int; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+type Decoder = Arc<dyn Fn(&[Parameter]) -> Box<dyn Instruction> + Send + Sync>;
+
+/// Custom opcodes that extend the standard Intcode instruction set.
+#[derive(Clone, Default)]
+pub struct OpcodeRegistry {
+    opcodes: HashMap<Int, CustomOpcode>,
+}
+
+#[derive(Clone)]
+struct CustomOpcode {
+    name: String,
+    parameter_count: usize,
+    decode: Decoder,
+}
+
+impl OpcodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `opcode` (the two lowest digits of an instruction) with
+    /// `decode` building the instruction from its decoded parameters.
+    ///
+    /// Panics if `opcode` is a standard opcode or is already registered.
+    pub fn register<F>(&mut self, opcode: Int, name: &str, parameter_count: usize, decode: F)
+    where
+        F: Fn(&[Parameter]) -> Box<dyn Instruction> + Send + Sync + 'static,
+    {
+        if !(0..100).contains(&opcode) || BUILTIN_OPCODES.contains(&opcode) {
+            panic!("can't register opcode {}", opcode);
+        }
+        if self.opcodes.contains_key(&opcode) {
+            panic!("opcode {} is already registered", opcode);
+        }
+        let custom_opcode = CustomOpcode {
+            name: name.to_string(),
+            parameter_count,
+            decode: Arc::new(decode),
+        };
+        self.opcodes.insert(opcode, custom_opcode);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.opcodes.is_empty()
+    }
+
+    pub fn name(&self, opcode: Int) -> Option<&str> {
+        self.opcodes.get(&opcode).map(|o| o.name.as_str())
+    }
+
+    pub fn parameter_count(&self, opcode: Int) -> Option<usize> {
+        self.opcodes.get(&opcode).map(|o| o.parameter_count)
+    }
+
+    pub(crate) fn decode(
+        &self,
+        opcode: Int,
+        memory: &Memory,
+        address: usize,
+    ) -> Option<Box<dyn Instruction>> {
+        let custom_opcode = self.opcodes.get(&opcode)?;
+        let count = custom_opcode.parameter_count;
+        let instruction: Vec<_> = (address..=address + count)
+            .map(|a| memory.read(a))
+            .collect();
+        let parameters = Parameter::from_instruction(&instruction, count);
+        Some((custom_opcode.decode)(&parameters))
+    }
+}
+
+impl fmt::Debug for OpcodeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut opcodes: Vec<_> = self.opcodes.iter().collect();
+        opcodes.sort_by_key(|&(&opcode, _)| opcode);
+        f.debug_map()
+            .entries(opcodes.iter().map(|(opcode, o)| (opcode, &o.name)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Program;
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct Increment {
+        address: Parameter,
+    }
+
+    impl Instruction for Increment {
+        fn run(&self, program: &mut Program) {
+            let address = program.evaluate_address(self.address);
+            let value = program.read_from_memory(address);
+            program.write_to_memory(address, value + 1);
+            program.set_instruction_pointer(program.instruction_pointer() + 2);
+        }
+    }
+
+    #[derive(Debug)]
+    struct Syscall {
+        number: Parameter,
+        log: Arc<Mutex<Vec<Int>>>,
+    }
+
+    impl Instruction for Syscall {
+        fn run(&self, program: &mut Program) {
+            let number = program.evaluate(self.number);
+            self.log.lock().unwrap().push(number);
+            program.push_output(number * 100);
+            program.set_instruction_pointer(program.instruction_pointer() + 2);
+        }
+    }
+
+    #[test]
+    fn test_custom_opcodes() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let syscall_log = Arc::clone(&log);
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(10, "inc", 1, |parameters| {
+            Box::new(Increment {
+                address: parameters[0],
+            })
+        });
+        opcodes.register(11, "syscall", 1, move |parameters| {
+            Box::new(Syscall {
+                number: parameters[0],
+                log: Arc::clone(&syscall_log),
+            })
+        });
+        assert_eq!(opcodes.name(11), Some("syscall"));
+        assert_eq!(opcodes.parameter_count(10), Some(1));
+        assert_eq!(format!("{:?}", opcodes), r#"{10: "inc", 11: "syscall"}"#);
+
+        let mut program = Program::from_input("10,9,10,9,11,9,111,7,99,40").with_opcodes(opcodes);
+        assert_eq!(program.run(&[]), vec![4200, 700]);
+        assert_eq!(*log.lock().unwrap(), vec![42, 7]);
+        assert!(program.is_finished());
+    }
+
+    #[test]
+    #[should_panic(expected = "can't register opcode 4")]
+    fn test_builtin_opcode() {
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(4, "out", 1, |_| unreachable!());
+    }
+}
@@ -0,0 +1,532 @@
+use crate::disasm::{DecodedInstruction, Disassembly, Operand, Operation};
+use crate::{Int, Program, ProgramState};
+use std::fmt;
+
+/// An instruction, or a run of instructions, rewritten by `optimize`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// An instruction that couldn't be improved on.
+    Instruction(DecodedInstruction),
+    /// An `add`, `mul`, `lt` or `eq` of two immediate values, folded into
+    /// storing the result.
+    Store {
+        address: usize,
+        value: Int,
+        destination: Operand,
+    },
+    /// A jump whose condition is always true.
+    Jump { address: usize, target: usize },
+    /// A jump whose condition is never true.
+    Nop { address: usize },
+    /// An `lt` or `eq` immediately followed by a jump on its result.
+    CompareAndBranch {
+        compare: DecodedInstruction,
+        jump: DecodedInstruction,
+    },
+    /// A folded store immediately followed by an unconditional jump, which is
+    /// how programs call functions.
+    StoreAndJump {
+        address: usize,
+        value: Int,
+        destination: Operand,
+        target: usize,
+    },
+}
+
+impl Op {
+    pub fn address(&self) -> usize {
+        match self {
+            Self::Instruction(instruction) => instruction.address,
+            Self::CompareAndBranch { compare, .. } => compare.address,
+            Self::Store { address, .. }
+            | Self::Jump { address, .. }
+            | Self::Nop { address }
+            | Self::StoreAndJump { address, .. } => *address,
+        }
+    }
+
+    /// The number of memory cells the op was made from.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Instruction(instruction) => instruction.size(),
+            Self::Store { .. } => 4,
+            Self::Jump { .. } | Self::Nop { .. } => 3,
+            Self::CompareAndBranch { compare, jump } => compare.size() + jump.size(),
+            Self::StoreAndJump { .. } => 7,
+        }
+    }
+}
+
+/// Optimizes the instructions in `disassembly`, in address order.
+///
+/// Arithmetic and comparisons of immediate values are folded into stores
+/// and jumps with immediate conditions become unconditional jumps or nops.
+/// Then adjacent pairs are fused into superinstructions: a comparison with
+/// the jump that tests its result, and a folded store with the jump after
+/// it.
+pub fn optimize(disassembly: &Disassembly) -> Vec<Op> {
+    let mut ops: Vec<Op> = vec![];
+    for instruction in disassembly.instructions() {
+        let op = simplify(instruction);
+        let fused = match (ops.last(), &op) {
+            (Some(previous), op) if previous.address() + previous.size() == op.address() => {
+                fuse(previous, op)
+            }
+            _ => None,
+        };
+        match fused {
+            Some(fused) => *ops.last_mut().unwrap() = fused,
+            None => ops.push(op),
+        }
+    }
+    ops
+}
+
+fn simplify(instruction: &DecodedInstruction) -> Op {
+    use Operand::Immediate;
+    let address = instruction.address;
+    let folded = match (instruction.operation, instruction.operands.as_slice()) {
+        // overflowing arithmetic is left for the VM, in case it never runs
+        (Operation::Add, &[Immediate(a), Immediate(b), destination]) => {
+            a.checked_add(b).map(|value| (value, destination))
+        }
+        (Operation::Multiply, &[Immediate(a), Immediate(b), destination]) => {
+            a.checked_mul(b).map(|value| (value, destination))
+        }
+        (Operation::LessThan, &[Immediate(a), Immediate(b), destination]) => {
+            Some(((a < b) as Int, destination))
+        }
+        (Operation::Equals, &[Immediate(a), Immediate(b), destination]) => {
+            Some(((a == b) as Int, destination))
+        }
+        _ => None,
+    };
+    if let Some((value, destination)) = folded {
+        return Op::Store {
+            address,
+            value,
+            destination,
+        };
+    }
+    match instruction.jump_target() {
+        Some(target) if instruction.is_unconditional_jump() => Op::Jump { address, target },
+        _ if instruction.is_never_taken() => Op::Nop { address },
+        _ => Op::Instruction(instruction.clone()),
+    }
+}
+
+fn fuse(first: &Op, second: &Op) -> Option<Op> {
+    match (first, second) {
+        (Op::Instruction(compare), Op::Instruction(jump)) => {
+            let is_compare = matches!(compare.operation, Operation::LessThan | Operation::Equals);
+            if !is_compare || !jump.is_jump() {
+                return None;
+            }
+            let destination = *compare.operands.last().unwrap();
+            // a comparison that overwrites the jump has to run on its own
+            let writes_jump = matches!(
+                destination,
+                Operand::Position(address) if address >= 0
+                    && (jump.address..jump.next_address()).contains(&(address as usize))
+            );
+            if !writes_jump && jump.jump_target().is_some() && jump.operands[0] == destination {
+                Some(Op::CompareAndBranch {
+                    compare: compare.clone(),
+                    jump: jump.clone(),
+                })
+            } else {
+                None
+            }
+        }
+        (
+            &Op::Store {
+                address,
+                value,
+                destination,
+            },
+            &Op::Jump { target, .. },
+        ) => Some(Op::StoreAndJump {
+            address,
+            value,
+            destination,
+            target,
+        }),
+        _ => None,
+    }
+}
+
+/// How much of a program `OptimizedProgram` has optimized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimizerStats {
+    pub folded: usize,
+    pub jumps: usize,
+    pub fused: usize,
+    /// Ops dropped because the program wrote to their instructions.
+    pub deoptimized: usize,
+}
+
+/// Runs a `Program` from its optimized instructions.
+///
+/// Instructions are optimized once, when the `OptimizedProgram` is created.
+/// When the program writes to the memory an op was made from, the op is
+/// dropped and those instructions are decoded from memory again each time
+/// they run, so self-modifying programs behave exactly as they would on
+/// their own. Programs with custom opcodes aren't optimized.
+#[derive(Debug, Clone)]
+pub struct OptimizedProgram {
+    program: Program,
+    /// The op starting at each address.
+    ops: Vec<Option<Op>>,
+    /// The address of the op made from each memory cell.
+    owners: Vec<Option<usize>>,
+    stats: OptimizerStats,
+}
+
+impl OptimizedProgram {
+    pub fn new(program: Program) -> Self {
+        let memory = program.memory();
+        let mut ops = vec![None; memory.len()];
+        let mut owners = vec![None; memory.len()];
+        let mut stats = OptimizerStats::default();
+        if program.opcodes.is_empty() {
+            for op in optimize(&Disassembly::new(&memory)) {
+                match op {
+                    Op::Instruction(_) => {}
+                    Op::Store { .. } => stats.folded += 1,
+                    Op::Jump { .. } | Op::Nop { .. } => stats.jumps += 1,
+                    Op::CompareAndBranch { .. } => stats.fused += 1,
+                    Op::StoreAndJump { .. } => {
+                        stats.folded += 1;
+                        stats.jumps += 1;
+                        stats.fused += 1;
+                    }
+                }
+                let address = op.address();
+                for owner in &mut owners[address..address + op.size()] {
+                    *owner = Some(address);
+                }
+                ops[address] = Some(op);
+            }
+        }
+        Self {
+            program,
+            ops,
+            owners,
+            stats,
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.program.is_finished()
+    }
+
+    pub fn stats(&self) -> OptimizerStats {
+        self.stats
+    }
+
+    /// The op at `address`, if it hasn't been deoptimized.
+    pub fn op_at(&self, address: usize) -> Option<&Op> {
+        self.ops.get(address)?.as_ref()
+    }
+
+    /// Runs like `Program::run`.
+    pub fn run(&mut self, inputs: &[Int]) -> Vec<Int> {
+        let program = &mut self.program;
+        program.outputs.clear();
+        program.inputs.extend_from_slice(inputs);
+        program.state = ProgramState::Running;
+        while self.program.state == ProgramState::Running {
+            self.step();
+        }
+        self.program.outputs.clone()
+    }
+
+    fn step(&mut self) {
+        let ip = self.program.instruction_pointer;
+        let op = match self.ops.get(ip) {
+            Some(Some(op)) => op,
+            _ => {
+                let write = self
+                    .program
+                    .decode_at(ip)
+                    .and_then(|i| i.write_address(self.program.relative_base));
+                self.program.step();
+                if let Some(address) = write {
+                    self.deoptimize(address);
+                }
+                return;
+            }
+        };
+        if let Some(address) = execute(op, &mut self.program) {
+            self.deoptimize(address);
+        }
+    }
+
+    fn deoptimize(&mut self, address: usize) {
+        let start = match self.owners.get(address) {
+            Some(&Some(start)) => start,
+            _ => return,
+        };
+        if let Some(op) = self.ops[start].take() {
+            for owner in &mut self.owners[start..start + op.size()] {
+                *owner = None;
+            }
+            self.stats.deoptimized += 1;
+        }
+    }
+}
+
+/// Executes `op`, returning the address it wrote to, if any.
+fn execute(op: &Op, program: &mut Program) -> Option<usize> {
+    match op {
+        Op::Instruction(instruction) => execute_instruction(instruction, program),
+        &Op::Store {
+            value, destination, ..
+        } => {
+            let address = write(program, destination, value);
+            program.instruction_pointer += 4;
+            program.steps += 1;
+            Some(address)
+        }
+        &Op::Jump { target, .. } => {
+            program.instruction_pointer = target;
+            program.steps += 1;
+            None
+        }
+        Op::Nop { .. } => {
+            program.instruction_pointer += 3;
+            program.steps += 1;
+            None
+        }
+        Op::CompareAndBranch { compare, jump } => {
+            let address = execute_instruction(compare, program)?;
+            // stop after the comparison if it overwrote the jump
+            if (jump.address..jump.next_address()).contains(&address) {
+                return Some(address);
+            }
+            execute_instruction(jump, program);
+            Some(address)
+        }
+        &Op::StoreAndJump {
+            address,
+            value,
+            destination,
+            target,
+        } => {
+            let written = write(program, destination, value);
+            program.steps += 1;
+            if (address + 4..address + 7).contains(&written) {
+                program.instruction_pointer = address + 4;
+            } else {
+                program.instruction_pointer = target;
+                program.steps += 1;
+            }
+            Some(written)
+        }
+    }
+}
+
+fn execute_instruction(instruction: &DecodedInstruction, program: &mut Program) -> Option<usize> {
+    let operands = &instruction.operands;
+    let value = |program: &Program, index: usize| match operands[index] {
+        Operand::Immediate(value) => value,
+        operand => program.read_from_memory(address(program, operand)),
+    };
+    let mut next = instruction.next_address();
+    let mut written = None;
+    match instruction.operation {
+        Operation::Add | Operation::Multiply | Operation::LessThan | Operation::Equals => {
+            let (a, b) = (value(program, 0), value(program, 1));
+            let result = match instruction.operation {
+                Operation::Add => a + b,
+                Operation::Multiply => a * b,
+                Operation::LessThan => (a < b) as Int,
+                _ => (a == b) as Int,
+            };
+            written = Some(write(program, operands[2], result));
+        }
+        Operation::Input => {
+            if program.inputs.is_empty() {
+                program.state = ProgramState::WaitingForInput;
+                return None;
+            }
+            let input = program.inputs.remove(0);
+            written = Some(write(program, operands[0], input));
+        }
+        Operation::Output => {
+            let output = value(program, 0);
+            program.outputs.push(output);
+        }
+        Operation::JumpIfTrue | Operation::JumpIfFalse => {
+            let condition = value(program, 0) != 0;
+            if condition == (instruction.operation == Operation::JumpIfTrue) {
+                next = value(program, 1) as usize;
+            }
+        }
+        Operation::RelativeBaseOffset => program.relative_base += value(program, 0),
+        Operation::Halt => {
+            program.state = ProgramState::Halted;
+            next = instruction.address;
+        }
+    }
+    program.instruction_pointer = next;
+    program.steps += 1;
+    written
+}
+
+fn address(program: &Program, operand: Operand) -> usize {
+    match operand {
+        Operand::Position(address) => address as usize,
+        Operand::Relative(offset) => (program.relative_base + offset) as usize,
+        Operand::Immediate(_) => panic!("can't interpret immediate mode parameter as address"),
+    }
+}
+
+fn write(program: &mut Program, destination: Operand, value: Int) -> usize {
+    let address = address(program, destination);
+    program.write_to_memory(address, value);
+    address
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instruction(instruction) => write!(f, "{}", instruction),
+            Self::Store {
+                value, destination, ..
+            } => write!(f, "store {}, {}", value, destination),
+            Self::Jump { target, .. } => write!(f, "jmp {}", target),
+            Self::Nop { .. } => write!(f, "nop"),
+            Self::CompareAndBranch { compare, jump } => write!(f, "{}; {}", compare, jump),
+            Self::StoreAndJump {
+                value,
+                destination,
+                target,
+                ..
+            } => write!(f, "store {}, {}; jmp {}", value, destination, target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compare_with_interpreter(image: &[Int], inputs: &[Int]) -> OptimizedProgram {
+        let mut program = Program::from_memory(image.to_vec());
+        let mut optimized = OptimizedProgram::new(program.clone());
+        assert_eq!(optimized.run(inputs), program.run(inputs));
+        assert_eq!(optimized.program(), &program);
+        assert_eq!(optimized.program().steps(), program.steps());
+        optimized
+    }
+
+    #[test]
+    fn test_optimize() {
+        let image = [
+            1101, 2, 3, 20, // store 5
+            1105, 0, 0, // never taken
+            1007, 20, 6, 21, 1005, 21, 18, // fused compare and branch
+            21101, 5, 0, 0, // fused store...
+            1106, 0, 4, // ...and jump
+        ];
+        let ops: Vec<_> = optimize(&Disassembly::new(&image))
+            .iter()
+            .map(Op::to_string)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                "store 5, [20]",
+                "nop",
+                "lt [20], 6, [21]; jnz [21], 18",
+                "store 5, [rb+0]; jmp 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overflow_not_folded() {
+        // the overflowing multiply is never run, so it mustn't stop the
+        // program from loading
+        let mut image = [
+            1005, 11, 8, // 0: jump to 8, as [11] is 1
+            1102, 0, 2, 0, // 3: [0] = Int::MAX * 2
+            99, 104, 1, 99, 1,
+        ];
+        image[4] = Int::MAX;
+        let ops = optimize(&Disassembly::new(&image));
+        assert!(ops.iter().any(|op| op.address() == 3));
+        assert!(!ops.iter().any(|op| matches!(op, Op::Store { .. })));
+        let mut optimized = OptimizedProgram::new(Program::from_memory(image.to_vec()));
+        assert_eq!(optimized.run(&[]), vec![1]);
+    }
+
+    #[test]
+    fn test_compiled_program() {
+        let source = "
+            fn main() {
+                let n = read();
+                while n != 0 {
+                    print(fib(n));
+                    n = read();
+                }
+            }
+
+            fn fib(n) {
+                if n < 2 {
+                    return n;
+                }
+                return fib(n - 1) + fib(n - 2);
+            }
+        ";
+        let image = crate::compile(source).unwrap();
+        let optimized = compare_with_interpreter(&image, &[10, 15, 0]);
+        assert_eq!(optimized.program().memory(), {
+            let mut program = Program::from_memory(image);
+            program.run(&[10, 15, 0]);
+            program.memory()
+        });
+        let stats = optimized.stats();
+        assert!(stats.folded > 0 && stats.jumps > 0 && stats.fused > 0);
+        assert_eq!(stats.deoptimized, 0);
+    }
+
+    #[test]
+    fn test_self_modifying_code() {
+        // outputs 2 + 3, then rewrites the folded add into a multiply and
+        // runs it again
+        let image = [
+            1101, 2, 3, 100, // 0: [100] = 2 + 3
+            4, 100, // 4: output [100]
+            1005, 101, 20, // 6: stop the second time around
+            1101, 1, 0, 101, // 9: [101] = 1
+            1101, 0, 1102, 0, // 13: replace the add with a multiply
+            1105, 1, 0, // 17: jump back
+            99,
+        ];
+        let optimized = compare_with_interpreter(&image, &[]);
+        assert_eq!(optimized.program().memory()[100], 6);
+        assert_eq!(optimized.stats().deoptimized, 1);
+        assert_eq!(optimized.op_at(0), None);
+    }
+
+    #[test]
+    fn test_input() {
+        let mut program = Program::from_input("3,9,1002,9,3,9,4,9,99,0");
+        let mut optimized = OptimizedProgram::new(program.clone());
+        assert_eq!(optimized.run(&[]), vec![]);
+        assert_eq!(optimized.program().state(), ProgramState::WaitingForInput);
+        assert_eq!(optimized.run(&[7]), vec![21]);
+        assert_eq!(program.run(&[7]), vec![21]);
+        assert_eq!(optimized.program(), &program);
+    }
+}
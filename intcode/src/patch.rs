@@ -0,0 +1,235 @@
+use crate::Int;
+use std::fmt;
+use std::str::FromStr;
+
+/// A list of memory writes to apply to a `Program` before it runs.
+///
+/// The text format has one `address=value` entry per line, optionally followed
+/// by `if old` to require that the address currently holds `old`. Blank lines
+/// and anything after a `#` are ignored:
+///
+/// ```text
+/// # enable free play
+/// 0=2 if 1
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    entries: Vec<PatchEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchEntry {
+    pub address: usize,
+    pub value: Int,
+    pub expected: Option<Int>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[PatchEntry] {
+        &self.entries
+    }
+
+    pub fn set(&mut self, address: usize, value: Int) {
+        self.push(PatchEntry {
+            address,
+            value,
+            expected: None,
+        });
+    }
+
+    pub fn replace(&mut self, address: usize, expected: Int, value: Int) {
+        self.push(PatchEntry {
+            address,
+            value,
+            expected: Some(expected),
+        });
+    }
+
+    pub fn push(&mut self, entry: PatchEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Builds the patch that turns the memory dump `before` into `after`, with
+    /// the old values as expectations. Cells past the end of a dump are zero.
+    pub fn diff(before: &[Int], after: &[Int]) -> Self {
+        let mut patch = Self::new();
+        for address in 0..before.len().max(after.len()) {
+            let old = before.get(address).copied().unwrap_or(0);
+            let new = after.get(address).copied().unwrap_or(0);
+            if old != new {
+                patch.replace(address, old, new);
+            }
+        }
+        patch
+    }
+}
+
+impl FromStr for Patch {
+    type Err = PatchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut patch = Self::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let entry = line.parse().map_err(|message| PatchError::Parse {
+                line: i + 1,
+                message,
+            })?;
+            patch.push(entry);
+        }
+        Ok(patch)
+    }
+}
+
+impl FromStr for PatchEntry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '=');
+        let address = parts.next().unwrap().trim();
+        let address = address
+            .parse()
+            .map_err(|_| format!("invalid address `{}`", address))?;
+        let rest = parts.next().ok_or("expected `address=value`")?;
+        let mut parts = rest.splitn(2, " if ");
+        let value = parts.next().unwrap().trim();
+        let value = value
+            .parse()
+            .map_err(|_| format!("invalid value `{}`", value))?;
+        let expected = match parts.next().map(str::trim) {
+            Some(expected) => Some(
+                expected
+                    .parse()
+                    .map_err(|_| format!("invalid expected value `{}`", expected))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            address,
+            value,
+            expected,
+        })
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PatchEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.address, self.value)?;
+        if let Some(expected) = self.expected {
+            write!(f, " if {}", expected)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Mismatch {
+        address: usize,
+        expected: Int,
+        found: Int,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            Self::Mismatch {
+                address,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {} at address {} but found {}",
+                expected, address, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Program;
+
+    #[test]
+    fn test_parse() {
+        let patch: Patch = "# free play\n0=2 if 1\n\n1000 = -5 # scratch\n"
+            .parse()
+            .unwrap();
+        let mut expected = Patch::new();
+        expected.replace(0, 1, 2);
+        expected.set(1000, -5);
+        assert_eq!(patch, expected);
+        assert_eq!(patch.to_string(), "0=2 if 1\n1000=-5\n");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "0=2\n1=x".parse::<Patch>().unwrap_err();
+        assert_eq!(
+            error,
+            PatchError::Parse {
+                line: 2,
+                message: String::from("invalid value `x`")
+            }
+        );
+        assert!("12".parse::<Patch>().is_err());
+        assert!("1=2 if".parse::<Patch>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut program = Program::from_input("1,0,0,0,99");
+        let patch: Patch = "1=12 if 0\n2=2 if 0".parse().unwrap();
+        program.apply_patch(&patch).unwrap();
+        assert_eq!(program.memory(), vec![1, 12, 2, 0, 99]);
+
+        let patch: Patch = "3=7\n4=0 if 98".parse().unwrap();
+        let error = program.apply_patch(&patch).unwrap_err();
+        assert_eq!(
+            error,
+            PatchError::Mismatch {
+                address: 4,
+                expected: 98,
+                found: 99
+            }
+        );
+        assert_eq!(program.read_from_memory(3), 0);
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = Program::from_input("1,9,10,3,2,3,11,0,99,30,40,50");
+        let mut after = before.clone();
+        after.run(&[]);
+        let patch = Patch::diff(&before.memory(), &after.memory());
+        assert_eq!(patch.to_string(), "0=3500 if 1\n3=70 if 3\n");
+        let mut patched = before.clone();
+        patched.apply_patch(&patch).unwrap();
+        assert_eq!(patched.memory(), after.memory());
+    }
+}
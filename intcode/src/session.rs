@@ -0,0 +1,333 @@
+use crate::{Int, Program, ProgramState};
+use std::fmt;
+use std::str::FromStr;
+
+/// The I/O of a program run, each event tagged with the number of
+/// instructions the program had executed when it happened.
+///
+/// Sessions are saved as text with one event per line:
+///
+/// ```text
+/// 0 in 5
+/// 31 out 1234
+/// 32 halt
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Input { step: u64, value: Int },
+    Output { step: u64, value: Int },
+    Halt { step: u64 },
+}
+
+/// What a replayed program did where it diverged from the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observation {
+    InputRequest { step: u64 },
+    Output { step: u64, value: Int },
+    Halt { step: u64 },
+}
+
+/// Where a replay first differed from the recorded session. `expected` is
+/// `None` when the program kept going after the session ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    pub index: usize,
+    pub expected: Option<Event>,
+    pub found: Observation,
+}
+
+impl Session {
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Runs `program`, feeding it the recorded inputs and checking every
+    /// output against the session. Succeeds if the program reproduces the
+    /// whole session, stopping where the recording stopped.
+    pub fn replay(&self, program: &mut Program) -> Result<(), Divergence> {
+        let mut index = 0;
+        program.outputs.clear();
+        program.state = ProgramState::Running;
+        loop {
+            let step = program.steps;
+            let observation = if program.opcode() == 3 && program.inputs.is_empty() {
+                match self.events.get(index) {
+                    Some(&Event::Input { step: s, value }) if s == step => {
+                        program.inputs.push(value);
+                        index += 1;
+                        continue;
+                    }
+                    None => {
+                        program.state = ProgramState::WaitingForInput;
+                        return Ok(());
+                    }
+                    _ => Observation::InputRequest { step },
+                }
+            } else {
+                let output_count = program.outputs.len();
+                program.step();
+                match program.outputs.get(output_count) {
+                    Some(&value) => Observation::Output { step, value },
+                    None if program.state == ProgramState::Halted => Observation::Halt { step },
+                    None => continue,
+                }
+            };
+            let expected = self.events.get(index).copied();
+            if expected.map(Observation::from) != Some(observation) {
+                return Err(Divergence {
+                    index,
+                    expected,
+                    found: observation,
+                });
+            }
+            index += 1;
+            if program.state == ProgramState::Halted {
+                return if index == self.events.len() {
+                    Ok(())
+                } else {
+                    Err(Divergence {
+                        index,
+                        expected: self.events.get(index).copied(),
+                        found: observation,
+                    })
+                };
+            }
+        }
+    }
+}
+
+impl From<Event> for Observation {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Input { step, .. } => Self::InputRequest { step },
+            Event::Output { step, value } => Self::Output { step, value },
+            Event::Halt { step } => Self::Halt { step },
+        }
+    }
+}
+
+/// Runs a `Program` like `Program::run` while recording a `Session`.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    program: Program,
+    session: Session,
+}
+
+impl Recorder {
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            session: Session::default(),
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn into_session(self) -> Session {
+        self.session
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.program.is_finished()
+    }
+
+    pub fn run(&mut self, inputs: &[Int]) -> Vec<Int> {
+        let program = &mut self.program;
+        let events = &mut self.session.events;
+        program.outputs.clear();
+        program.inputs.extend_from_slice(inputs);
+        program.state = ProgramState::Running;
+        while program.state == ProgramState::Running {
+            let step = program.steps;
+            let next_input = program.inputs.first().copied();
+            let input_count = program.inputs.len();
+            let output_count = program.outputs.len();
+            program.step();
+            if program.inputs.len() < input_count {
+                let value = next_input.unwrap();
+                events.push(Event::Input { step, value });
+            }
+            if let Some(&value) = program.outputs.get(output_count) {
+                events.push(Event::Output { step, value });
+            }
+            if program.state == ProgramState::Halted {
+                events.push(Event::Halt { step });
+            }
+        }
+        program.outputs.clone()
+    }
+}
+
+impl FromStr for Session {
+    type Err = ParseSessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut events = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let event = line.parse().map_err(|message| ParseSessionError {
+                line: i + 1,
+                message,
+            })?;
+            events.push(event);
+        }
+        Ok(Self { events })
+    }
+}
+
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<_> = s.split_whitespace().collect();
+        let step = words[0]
+            .parse()
+            .map_err(|_| format!("invalid step `{}`", words[0]))?;
+        let value = |word: &str| {
+            word.parse()
+                .map_err(|_| format!("invalid value `{}`", word))
+        };
+        match words[1..] {
+            ["in", v] => Ok(Self::Input {
+                step,
+                value: value(v)?,
+            }),
+            ["out", v] => Ok(Self::Output {
+                step,
+                value: value(v)?,
+            }),
+            ["halt"] => Ok(Self::Halt { step }),
+            _ => Err(String::from("expected `in VALUE`, `out VALUE` or `halt`")),
+        }
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Input { step, value } => write!(f, "{} in {}", step, value),
+            Self::Output { step, value } => write!(f, "{} out {}", step, value),
+            Self::Halt { step } => write!(f, "{} halt", step),
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "event {}: ", self.index)?;
+        match self.found {
+            Observation::InputRequest { step } => write!(f, "input requested at step {}", step)?,
+            Observation::Output { step, value } => write!(f, "{} out {}", step, value)?,
+            Observation::Halt { step } => write!(f, "{} halt", step)?,
+        }
+        match self.expected {
+            Some(event) => write!(f, ", expected {}", event),
+            None => write!(f, ", expected end of session"),
+        }
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSessionError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseSessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseSessionError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Outputs 1 if the input is 8, otherwise 0, twice.
+    const IS_EIGHT: &str = "3,12,8,12,11,12,4,12,1105,1,0,8,0";
+
+    fn record() -> Session {
+        let mut recorder = Recorder::new(Program::from_input(IS_EIGHT));
+        assert_eq!(recorder.run(&[8]), vec![1]);
+        assert_eq!(recorder.run(&[3]), vec![0]);
+        recorder.into_session()
+    }
+
+    #[test]
+    fn test_record() {
+        let session = record();
+        assert_eq!(session.to_string(), "0 in 8\n2 out 1\n4 in 3\n6 out 0\n");
+        assert_eq!(session.to_string().parse(), Ok(session));
+
+        let mut recorder = Recorder::new(Program::from_input("104,5,99"));
+        recorder.run(&[]);
+        assert_eq!(recorder.session().to_string(), "0 out 5\n1 halt\n");
+    }
+
+    #[test]
+    fn test_replay() {
+        let session = record();
+        let mut program = Program::from_input(IS_EIGHT);
+        assert_eq!(session.replay(&mut program), Ok(()));
+        assert_eq!(program.state(), ProgramState::WaitingForInput);
+        assert_eq!(program.steps(), 8);
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let session = record();
+        // compares with 3 instead of 8
+        let mut program = Program::from_input("3,12,8,12,11,12,4,12,1105,1,0,3,0");
+        let divergence = session.replay(&mut program).unwrap_err();
+        assert_eq!(
+            divergence,
+            Divergence {
+                index: 1,
+                expected: Some(Event::Output { step: 2, value: 1 }),
+                found: Observation::Output { step: 2, value: 0 },
+            }
+        );
+        assert_eq!(divergence.to_string(), "event 1: 2 out 0, expected 2 out 1");
+
+        // halts after the first output
+        let mut program = Program::from_input("3,12,8,12,11,12,4,12,99,0,0,8,0");
+        let divergence = session.replay(&mut program).unwrap_err();
+        assert_eq!(divergence.index, 2);
+        assert_eq!(divergence.found, Observation::Halt { step: 3 });
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "0 in 1\n5 out".parse::<Session>().unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!("x halt".parse::<Session>().is_err());
+        assert!("1 in y".parse::<Session>().is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{Int, Program, ProgramState};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Programs are equal when their observable state is: memory, instruction
+/// pointer, relative base, run state and pending inputs and outputs. Custom
+/// opcodes are not compared.
+impl PartialEq for Program {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_execution_state(other)
+            && self.state == other.state
+            && self.inputs == other.inputs
+            && self.outputs == other.outputs
+    }
+}
+
+impl Eq for Program {}
+
+impl Hash for Program {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state.hash(state);
+        self.instruction_pointer.hash(state);
+        self.relative_base.hash(state);
+        self.inputs.hash(state);
+        self.outputs.hash(state);
+        self.memory.hash(state);
+    }
+}
+
+impl Program {
+    /// A hash of the observable state that is the same on every run, unlike
+    /// the randomly seeded hashers in `std::collections`.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Like `run`, but stops with an error if the program enters a state it
+    /// has already been in since it last read an input, which means it would
+    /// never read another input or halt.
+    ///
+    /// States are sampled whenever a jump doesn't move forward and compared
+    /// using Brent's algorithm, so memory use stays constant however long the
+    /// program runs.
+    pub fn run_detecting_loops(&mut self, inputs: &[Int]) -> Result<Vec<Int>, InfiniteLoop> {
+        self.outputs.clear();
+        self.inputs.extend_from_slice(inputs);
+        self.state = ProgramState::Running;
+        let mut saved = self.clone();
+        let mut power = 1;
+        let mut length = 0;
+        while self.state == ProgramState::Running {
+            let instruction_pointer = self.instruction_pointer;
+            let input_count = self.inputs.len();
+            self.step();
+            if self.inputs.len() != input_count {
+                saved = self.clone();
+                power = 1;
+                length = 0;
+                continue;
+            }
+            if self.instruction_pointer > instruction_pointer {
+                continue;
+            }
+            if self.same_execution_state(&saved) {
+                return Err(InfiniteLoop {
+                    instruction_pointer: self.instruction_pointer,
+                    outputs: self.outputs.clone(),
+                });
+            }
+            length += 1;
+            if length == power {
+                saved = self.clone();
+                power *= 2;
+                length = 0;
+            }
+        }
+        Ok(self.outputs.clone())
+    }
+
+    fn same_execution_state(&self, other: &Self) -> bool {
+        self.instruction_pointer == other.instruction_pointer
+            && self.relative_base == other.relative_base
+            && self.memory == other.memory
+    }
+}
+
+/// Returned by `Program::run_detecting_loops` when the program repeats a
+/// state, with the outputs produced before the loop was detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfiniteLoop {
+    pub instruction_pointer: usize,
+    pub outputs: Vec<Int>,
+}
+
+impl fmt::Display for InfiniteLoop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "program loops forever at address {}",
+            self.instruction_pointer
+        )
+    }
+}
+
+impl std::error::Error for InfiniteLoop {}
+
+// 64-bit FNV-1a
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    // the shared memory copy counters aren't part of the hash
+    #[allow(clippy::mutable_key_type)]
+    fn test_equality() {
+        let program = Program::from_input("3,9,4,9,1105,1,0,99,0,0");
+        let mut a = program.clone();
+        let mut b = program.clone();
+        assert_eq!(a, b);
+        a.run(&[4]);
+        b.run(&[5]);
+        assert_ne!(a, b);
+        b.run(&[]);
+        assert_ne!(a, b);
+        b.write_to_memory(9, 4);
+        b.outputs = vec![4];
+        assert_eq!(a, b);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        let states: HashSet<_> = vec![program.clone(), a, b].into_iter().collect();
+        assert_eq!(states.len(), 2);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let a = Program::from_input("1,0,0,0,99");
+        let mut b = Program::from_input("1,0,0,0,99,0,0");
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        b.run(&[]);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn test_detect_loop() {
+        // outputs the input, then spins forever
+        let mut program = Program::from_input("3,20,4,20,1105,1,4");
+        let error = program.run_detecting_loops(&[7]).unwrap_err();
+        assert_eq!(error.instruction_pointer, 4);
+        assert_eq!(error.outputs, vec![7]);
+
+        // counts down from the input and halts
+        let mut program = Program::from_input("3,20,1001,20,-1,20,1005,20,2,4,20,99");
+        assert_eq!(program.run_detecting_loops(&[1000]), Ok(vec![0]));
+    }
+
+    #[test]
+    fn test_detect_loop_with_output() {
+        // outputs 1 forever
+        let mut program = Program::from_input("104,1,1105,1,0");
+        let error = program.run_detecting_loops(&[]).unwrap_err();
+        assert_eq!(error.instruction_pointer, 0);
+        assert_eq!(error.outputs, vec![1]);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{Int, Program, ProgramState};
+use std::any::Any;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+/// A `Program` running on its own thread.
+///
+/// Values sent on `input` are consumed by `Input` instructions, blocking the
+/// thread while none are available, and each output is sent on `output` as
+/// soon as it is produced. `output` disconnects once the program halts.
+#[derive(Debug)]
+pub struct ProgramHandle {
+    pub input: Sender<Int>,
+    pub output: Receiver<Int>,
+    thread: JoinHandle<Result<Program, ThreadError>>,
+}
+
+impl ProgramHandle {
+    pub(crate) fn spawn(program: Program) -> Self {
+        let (input, inputs) = mpsc::channel();
+        let (outputs, output) = mpsc::channel();
+        let thread = thread::spawn(move || run(program, inputs, outputs));
+        Self {
+            input,
+            output,
+            thread,
+        }
+    }
+
+    /// Closes `input` and waits for the program to finish.
+    pub fn join(self) -> Result<Program, ThreadError> {
+        let Self { input, thread, .. } = self;
+        drop(input);
+        thread.join().map_err(ThreadError::from_panic)?
+    }
+}
+
+fn run(
+    mut program: Program,
+    inputs: Receiver<Int>,
+    outputs: Sender<Int>,
+) -> Result<Program, ThreadError> {
+    program.outputs.clear();
+    program.state = ProgramState::Running;
+    loop {
+        match program.state {
+            ProgramState::Halted => return Ok(program),
+            ProgramState::WaitingForInput => match inputs.recv() {
+                Ok(value) => {
+                    program.inputs.push(value);
+                    program.state = ProgramState::Running;
+                }
+                Err(_) => return Err(ThreadError::InputClosed(Box::new(program))),
+            },
+            _ => {
+                program.step();
+                if let Some(value) = program.outputs.pop() {
+                    // the host may stop listening before the program halts
+                    let _ = outputs.send(value);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ThreadError {
+    /// The program needed input after the handle's `input` was dropped.
+    InputClosed(Box<Program>),
+    Panicked(String),
+}
+
+impl ThreadError {
+    fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => String::from("unknown panic"),
+            },
+        };
+        Self::Panicked(message)
+    }
+}
+
+impl fmt::Display for ThreadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InputClosed(_) => write!(f, "input closed while program was waiting for input"),
+            Self::Panicked(message) => write!(f, "program panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ThreadError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Reads a value into address 20 and outputs it doubled, forever.
+    const DOUBLER: &str = "3,20,1002,20,2,21,4,21,1105,1,0";
+
+    #[test]
+    fn test_channels() {
+        let handle = Program::from_input(DOUBLER).spawn();
+        for i in 0..10 {
+            handle.input.send(i).unwrap();
+        }
+        let outputs: Vec<_> = handle.output.iter().take(10).collect();
+        assert_eq!(outputs, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+        match handle.join() {
+            Err(ThreadError::InputClosed(program)) => {
+                assert_eq!(program.state(), ProgramState::WaitingForInput);
+                assert_eq!(program.read_from_memory(21), 18);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_join_halted() {
+        let handle = Program::from_input("3,9,4,9,4,9,99,0,0,0").spawn();
+        handle.input.send(7).unwrap();
+        assert_eq!(handle.output.iter().collect::<Vec<_>>(), vec![7, 7]);
+        let program = handle.join().unwrap();
+        assert!(program.is_finished());
+    }
+
+    #[test]
+    fn test_panic() {
+        let handle = Program::from_input("42").spawn();
+        match handle.join() {
+            Err(ThreadError::Panicked(message)) => assert_eq!(message, "Invalid operation"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
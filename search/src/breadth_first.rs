@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// Everything reachable from a start node, with how many steps away each
+/// node is and the way back to the start.
+#[derive(Debug, Clone)]
+pub struct Bfs<N> {
+    start: N,
+    distances: HashMap<N, usize>,
+    parents: HashMap<N, N>,
+}
+
+impl<N: Clone + Eq + Hash> Bfs<N> {
+    pub fn start(&self) -> &N {
+        &self.start
+    }
+
+    /// The number of steps from the start to `node`, or `None` if it can't be
+    /// reached.
+    pub fn distance(&self, node: &N) -> Option<usize> {
+        self.distances.get(node).copied()
+    }
+
+    pub fn distances(&self) -> &HashMap<N, usize> {
+        &self.distances
+    }
+
+    /// A shortest path from the start to `node`, including both ends.
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.distances.contains_key(node) {
+            return None;
+        }
+        let mut path = vec![node.clone()];
+        let mut node = node;
+        while let Some(parent) = self.parents.get(node) {
+            path.push(parent.clone());
+            node = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// How many nodes can be reached, including the start.
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    /// Always false, since the start is always reached.
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// The number of steps to the furthest node.
+    pub fn max_distance(&self) -> usize {
+        self.distances.values().copied().max().unwrap_or(0)
+    }
+}
+
+/// Explores every node reachable from `start`.
+pub fn bfs<N, F, I>(start: N, mut neighbors: F) -> Bfs<N>
+where
+    N: Clone + Eq + Hash,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut distances = HashMap::new();
+    let mut parents = HashMap::new();
+    let mut queue = VecDeque::new();
+    distances.insert(start.clone(), 0);
+    queue.push_back(start.clone());
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[&node] + 1;
+        for next in neighbors(&node) {
+            if distances.contains_key(&next) {
+                continue;
+            }
+            distances.insert(next.clone(), distance);
+            parents.insert(next.clone(), node.clone());
+            queue.push_back(next);
+        }
+    }
+    Bfs {
+        start,
+        distances,
+        parents,
+    }
+}
+
+/// A shortest path from `start` to the first node found that `is_goal`
+/// accepts, including both ends. Unlike `bfs` this stops as soon as the goal
+/// is reached.
+pub fn shortest_path<N, G, F, I>(start: N, mut is_goal: G, mut neighbors: F) -> Option<Vec<N>>
+where
+    N: Clone + Eq + Hash,
+    G: FnMut(&N) -> bool,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut parents = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start.clone());
+    queue.push_back(start);
+    while let Some(node) = queue.pop_front() {
+        if is_goal(&node) {
+            let mut path = vec![node];
+            while let Some(parent) = parents.remove(path.last().unwrap()) {
+                path.push(parent);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in neighbors(&node) {
+            if seen.insert(next.clone()) {
+                parents.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Spreads out from `starts` a step at a time, returning the nodes first
+/// reached at each step. The first layer is the starts themselves, so the
+/// number of steps to fill everything is one less than the number of layers.
+pub fn flood_fill<N, S, F, I>(starts: S, mut neighbors: F) -> Vec<Vec<N>>
+where
+    N: Clone + Eq + Hash,
+    S: IntoIterator<Item = N>,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut seen = HashSet::new();
+    let mut layer: Vec<N> = starts
+        .into_iter()
+        .filter(|node| seen.insert(node.clone()))
+        .collect();
+    let mut layers = vec![];
+    while !layer.is_empty() {
+        let mut next_layer = vec![];
+        for node in &layer {
+            for next in neighbors(node) {
+                if seen.insert(next.clone()) {
+                    next_layer.push(next);
+                }
+            }
+        }
+        layers.push(layer);
+        layer = next_layer;
+    }
+    layers
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 0 - 1 - 2 - 3    6 - 7
+    //     |       |
+    //     4 ----- 5
+    fn neighbors(&node: &u32) -> Vec<u32> {
+        let edges = [(0, 1), (1, 2), (2, 3), (1, 4), (4, 5), (5, 3), (6, 7)];
+        edges
+            .iter()
+            .filter_map(|&(a, b)| match node {
+                _ if node == a => Some(b),
+                _ if node == b => Some(a),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let search = bfs(0, neighbors);
+        assert_eq!(search.len(), 6);
+        assert_eq!(search.distance(&3), Some(3));
+        assert_eq!(search.distance(&5), Some(3));
+        assert_eq!(search.distance(&6), None);
+        assert_eq!(search.max_distance(), 3);
+        assert_eq!(search.path_to(&4), Some(vec![0, 1, 4]));
+        assert_eq!(search.path_to(&0), Some(vec![0]));
+        assert_eq!(search.path_to(&7), None);
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        assert_eq!(
+            shortest_path(0, |&n| n == 3, neighbors),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(shortest_path(6, |&n| n == 3, neighbors), None);
+        let mut visited = 0;
+        let path = shortest_path(
+            0,
+            |&n| n == 1,
+            |&n| {
+                visited += 1;
+                neighbors(&n)
+            },
+        );
+        assert_eq!(path, Some(vec![0, 1]));
+        assert_eq!(visited, 1);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let layers = flood_fill(vec![0], neighbors);
+        assert_eq!(layers.len(), 4);
+        let mut last = layers[3].clone();
+        last.sort_unstable();
+        assert_eq!(last, vec![3, 5]);
+        let layers = flood_fill(vec![3, 6, 3], neighbors);
+        assert_eq!(layers[0], vec![3, 6]);
+        assert_eq!(layers.iter().map(Vec::len).sum::<usize>(), 8);
+    }
+}
@@ -0,0 +1,131 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// The cheapest path from `start` to a node that `is_goal` accepts, and what
+/// it costs, where `neighbors` gives each neighbor with the cost of moving to
+/// it. Costs must not be negative.
+pub fn dijkstra<N, C, G, F, I>(start: N, is_goal: G, neighbors: F) -> Option<(Vec<N>, C)>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    G: FnMut(&N) -> bool,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, is_goal, neighbors, |_| C::default())
+}
+
+/// Like `dijkstra`, but guided by `heuristic`, an estimate of the cost from a
+/// node to the nearest goal. The path is only guaranteed to be the cheapest
+/// if the estimate never exceeds the real cost.
+pub fn astar<N, C, G, F, I, H>(
+    start: N,
+    mut is_goal: G,
+    mut neighbors: F,
+    mut heuristic: H,
+) -> Option<(Vec<N>, C)>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    G: FnMut(&N) -> bool,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, C)>,
+    H: FnMut(&N) -> C,
+{
+    // nodes are referred to by index so the heap doesn't need them to be Ord
+    let mut nodes = vec![start.clone()];
+    let mut indices = HashMap::new();
+    indices.insert(start.clone(), 0);
+    let mut costs = vec![C::default()];
+    let mut parents = vec![None];
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((heuristic(&start), C::default(), 0)));
+    while let Some(Reverse((_, cost, index))) = queue.pop() {
+        if cost > costs[index] {
+            continue;
+        }
+        if is_goal(&nodes[index]) {
+            let mut path = vec![];
+            let mut current = Some(index);
+            while let Some(i) = current {
+                path.push(nodes[i].clone());
+                current = parents[i];
+            }
+            path.reverse();
+            return Some((path, cost));
+        }
+        for (next, step) in neighbors(&nodes[index]) {
+            let next_cost = cost + step;
+            let next_index = match indices.get(&next) {
+                Some(&i) if costs[i] <= next_cost => continue,
+                Some(&i) => i,
+                None => {
+                    nodes.push(next.clone());
+                    costs.push(next_cost);
+                    parents.push(None);
+                    indices.insert(next.clone(), nodes.len() - 1);
+                    nodes.len() - 1
+                }
+            };
+            costs[next_index] = next_cost;
+            parents[next_index] = Some(index);
+            let estimate = next_cost + heuristic(&next);
+            queue.push(Reverse((estimate, next_cost, next_index)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A 5x5 grid where each cell costs its digit to enter.
+    const COSTS: [&str; 5] = ["11637", "13813", "21365", "36949", "74634"];
+
+    fn neighbors(&(x, y): &(i32, i32)) -> Vec<((i32, i32), u32)> {
+        let steps = [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)];
+        steps
+            .iter()
+            .filter(|&&(x, y)| (0..5).contains(&x) && (0..5).contains(&y))
+            .map(|&(x, y)| {
+                let cost = COSTS[y as usize].as_bytes()[x as usize] - b'0';
+                ((x, y), u32::from(cost))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let (path, cost) = dijkstra((0, 0), |&n| n == (4, 4), neighbors).unwrap();
+        assert_eq!(cost, 24);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(4, 4)));
+        let total: u32 = path[1..]
+            .iter()
+            .map(|&(x, y)| u32::from(COSTS[y as usize].as_bytes()[x as usize] - b'0'))
+            .sum();
+        assert_eq!(total, cost);
+        assert_eq!(dijkstra((0, 0), |_| false, neighbors), None);
+        assert_eq!(dijkstra(7, |&n| n == 7, |_| vec![]), Some((vec![7], 0)));
+    }
+
+    #[test]
+    fn test_astar() {
+        let mut expanded = 0;
+        let manhattan = |&(x, y): &(i32, i32)| (4 - x + 4 - y) as u32;
+        let result = astar(
+            (0, 0),
+            |&n| n == (4, 4),
+            |n| {
+                expanded += 1;
+                neighbors(n)
+            },
+            manhattan,
+        );
+        assert_eq!(result.unwrap().1, 24);
+        assert!(expanded < 25);
+    }
+}
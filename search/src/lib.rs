@@ -0,0 +1,8 @@
+//! Searches over graphs given as a start node and a function listing each
+//! node's neighbors, so mazes, trees and state spaces can share them.
+
+mod breadth_first;
+mod dijkstra;
+
+pub use breadth_first::{bfs, flood_fill, shortest_path, Bfs};
+pub use dijkstra::{astar, dijkstra};
@@ -0,0 +1,47 @@
+use grid::Grid;
+
+/// How many pixels of each color a layer has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ColorCounts([usize; 10]);
+
+impl ColorCounts {
+    /// Counts a layer's colors, which must all be digits, as `SpaceImage`
+    /// makes sure they are.
+    pub(crate) fn of(layer: &Grid<u8>) -> Self {
+        let mut counts = [0; 10];
+        for (_, &color) in layer.iter() {
+            counts[usize::from(color)] += 1;
+        }
+        Self(counts)
+    }
+
+    pub fn count(&self, color: u8) -> usize {
+        self.0.get(usize::from(color)).copied().unwrap_or(0)
+    }
+
+    /// The number of pixels in the layer.
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
+    /// The colors that appear at least once, from 0 up.
+    pub fn colors(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..10).filter(move |&color| self.count(color) > 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_counts() {
+        let layer = Grid::from_rows(vec![vec![0, 1, 2], vec![2, 2, 9]]);
+        let counts = ColorCounts::of(&layer);
+        assert_eq!(counts.count(2), 3);
+        assert_eq!(counts.count(5), 0);
+        assert_eq!(counts.count(10), 0);
+        assert_eq!(counts.total(), 6);
+        assert_eq!(counts.colors().collect::<Vec<_>>(), vec![0, 1, 2, 9]);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::ColorCounts;
+use grid::Grid;
+use std::fmt;
+
+/// The color the puzzles use for transparent pixels.
+pub const TRANSPARENT: u8 = 2;
+
+/// An image made of layers that are all the same size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceImage {
+    width: usize,
+    height: usize,
+    layers: Vec<Grid<u8>>,
+}
+
+impl SpaceImage {
+    /// Reads an image `width` pixels wide and `height` tall from its digits.
+    /// Whitespace around the digits is ignored.
+    pub fn decode(data: &str, width: usize, height: usize) -> Result<Self, SifError> {
+        if width == 0 || height == 0 {
+            return Err(SifError::NoPixels { width, height });
+        }
+        let layer_size = width
+            .checked_mul(height)
+            .ok_or(SifError::TooLarge { width, height })?;
+        let digits = data
+            .trim()
+            .chars()
+            .enumerate()
+            .map(|(position, c)| match c.to_digit(10) {
+                Some(digit) => Ok(digit as u8),
+                None => Err(SifError::InvalidDigit { position, found: c }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if digits.is_empty() {
+            return Err(SifError::NoLayers);
+        }
+        if digits.len() % layer_size != 0 {
+            return Err(SifError::WrongLength {
+                length: digits.len(),
+                layer_size,
+            });
+        }
+        let layers = digits
+            .chunks(layer_size)
+            .map(|layer| Grid::from_rows(layer.chunks(width).map(|row| row.iter().copied())))
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            layers,
+        })
+    }
+
+    /// An image made of `layers`, the first one on top.
+    pub fn from_layers(layers: Vec<Grid<u8>>) -> Result<Self, SifError> {
+        let first = layers.first().ok_or(SifError::NoLayers)?;
+        let (width, height) = (first.width(), first.height());
+        if width == 0 || height == 0 {
+            return Err(SifError::NoPixels { width, height });
+        }
+        for (index, layer) in layers.iter().enumerate() {
+            if (layer.width(), layer.height()) != (width, height) {
+                return Err(SifError::MismatchedLayer {
+                    layer: index,
+                    width: layer.width(),
+                    height: layer.height(),
+                    expected: (width, height),
+                });
+            }
+            if let Some(&color) = layer.iter().map(|(_, color)| color).find(|&&c| c > 9) {
+                return Err(SifError::InvalidColor {
+                    layer: index,
+                    color,
+                });
+            }
+        }
+        Ok(Self {
+            width,
+            height,
+            layers,
+        })
+    }
+
+    /// A single layer image of `bitmap`, ready to be encoded.
+    pub fn from_bitmap(bitmap: &Grid<u8>) -> Result<Self, SifError> {
+        Self::from_layers(vec![bitmap.clone()])
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The layers, top first.
+    pub fn layers(&self) -> &[Grid<u8>] {
+        &self.layers
+    }
+
+    /// How often each color appears in each layer, top first.
+    pub fn layer_counts(&self) -> Vec<ColorCounts> {
+        self.layers.iter().map(ColorCounts::of).collect()
+    }
+
+    /// The digits of every layer in order, which `decode` reads back.
+    pub fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    /// What the image looks like with the layers stacked, where `transparent`
+    /// pixels let the layer below show through. Pixels that are transparent
+    /// in every layer stay `transparent`.
+    pub fn composite(&self, transparent: u8) -> Grid<u8> {
+        let mut image = Grid::new(self.width, self.height, transparent);
+        for layer in self.layers.iter().rev() {
+            for (point, &color) in layer.iter() {
+                if color != transparent {
+                    image[point] = color;
+                }
+            }
+        }
+        image
+    }
+}
+
+impl fmt::Display for SpaceImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for layer in &self.layers {
+            for (_, color) in layer.iter() {
+                write!(f, "{}", color)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SifError {
+    /// The image was asked to be 0 pixels wide or tall.
+    NoPixels {
+        width: usize,
+        height: usize,
+    },
+    NoLayers,
+    /// The image was asked to have more pixels per layer than fit in memory.
+    TooLarge {
+        width: usize,
+        height: usize,
+    },
+    /// `position` counts characters after any leading whitespace.
+    InvalidDigit {
+        position: usize,
+        found: char,
+    },
+    /// The digits don't split evenly into layers.
+    WrongLength {
+        length: usize,
+        layer_size: usize,
+    },
+    MismatchedLayer {
+        layer: usize,
+        width: usize,
+        height: usize,
+        expected: (usize, usize),
+    },
+    InvalidColor {
+        layer: usize,
+        color: u8,
+    },
+}
+
+impl fmt::Display for SifError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPixels { width, height } => {
+                write!(f, "a {}x{} image has no pixels", width, height)
+            }
+            Self::NoLayers => write!(f, "an image needs at least one layer"),
+            Self::TooLarge { width, height } => {
+                write!(f, "a {}x{} image has too many pixels", width, height)
+            }
+            Self::InvalidDigit { position, found } => {
+                write!(f, "position {}: {:?} isn't a digit", position, found)
+            }
+            Self::WrongLength { length, layer_size } => write!(
+                f,
+                "{} digits don't split into layers of {}",
+                length, layer_size
+            ),
+            Self::MismatchedLayer {
+                layer,
+                width,
+                height,
+                expected: (expected_width, expected_height),
+            } => write!(
+                f,
+                "layer {} is {}x{} instead of {}x{}",
+                layer, width, height, expected_width, expected_height
+            ),
+            Self::InvalidColor { layer, color } => {
+                write!(f, "layer {}: {} isn't a color from 0 to 9", layer, color)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SifError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let image = SpaceImage::decode("123456789012\n", 3, 2).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert_eq!(image.layers().len(), 2);
+        assert_eq!(image.layers()[1].to_string(), "789\n012\n");
+        assert_eq!(image.layer_counts()[0].count(1), 1);
+        assert_eq!(image.encode(), "123456789012");
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            SpaceImage::decode("1234567", 3, 2),
+            Err(SifError::WrongLength {
+                length: 7,
+                layer_size: 6
+            })
+        );
+        assert_eq!(
+            SpaceImage::decode(" 12a4", 2, 2),
+            Err(SifError::InvalidDigit {
+                position: 2,
+                found: 'a'
+            })
+        );
+        assert_eq!(SpaceImage::decode("\n", 2, 2), Err(SifError::NoLayers));
+        let error = SpaceImage::decode("12", 0, 2).unwrap_err();
+        assert_eq!(error.to_string(), "a 0x2 image has no pixels");
+        assert_eq!(
+            SpaceImage::decode("1", usize::MAX, 2),
+            Err(SifError::TooLarge {
+                width: usize::MAX,
+                height: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_composite() {
+        let image = SpaceImage::decode("0222112222120000", 2, 2).unwrap();
+        assert_eq!(image.composite(TRANSPARENT).to_string(), "01\n10\n");
+        // with 0 transparent instead the top 2s cover all but the first pixel
+        assert_eq!(image.composite(0).to_string(), "12\n22\n");
+        let hidden = SpaceImage::decode("2222", 2, 2).unwrap();
+        assert_eq!(hidden.composite(TRANSPARENT).to_string(), "22\n22\n");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = "0222112222120000";
+        let image = SpaceImage::decode(data, 2, 2).unwrap();
+        assert_eq!(SpaceImage::decode(&image.encode(), 2, 2), Ok(image.clone()));
+        let rebuilt = SpaceImage::from_layers(image.layers().to_vec()).unwrap();
+        assert_eq!(rebuilt.encode(), data);
+
+        let bitmap = image.composite(TRANSPARENT);
+        let flattened = SpaceImage::from_bitmap(&bitmap).unwrap();
+        assert_eq!(flattened.encode(), "0110");
+        assert_eq!(flattened.composite(TRANSPARENT), bitmap);
+    }
+
+    #[test]
+    fn test_from_layers_errors() {
+        let small = Grid::new(2, 2, 0);
+        let wide = Grid::new(3, 2, 0);
+        assert_eq!(
+            SpaceImage::from_layers(vec![small.clone(), wide]),
+            Err(SifError::MismatchedLayer {
+                layer: 1,
+                width: 3,
+                height: 2,
+                expected: (2, 2)
+            })
+        );
+        assert_eq!(
+            SpaceImage::from_bitmap(&Grid::new(1, 1, 12)),
+            Err(SifError::InvalidColor {
+                layer: 0,
+                color: 12
+            })
+        );
+        assert_eq!(SpaceImage::from_layers(vec![]), Err(SifError::NoLayers));
+        assert!(SpaceImage::from_bitmap(&small).is_ok());
+    }
+}
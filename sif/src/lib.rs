@@ -0,0 +1,11 @@
+//! The Space Image Format: pictures sent as a stream of digits, split into
+//! equally sized layers that are stacked with the first layer on top.
+//!
+//! Each digit is a color, and one of them is usually transparent so the
+//! layers underneath show through.
+
+mod counts;
+mod image;
+
+pub use counts::ColorCounts;
+pub use image::{SifError, SpaceImage, TRANSPARENT};